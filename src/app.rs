@@ -1,29 +1,55 @@
+use crate::diagnostic::{self, Diagnostic, DiagnosticFormat};
+use crate::formatter;
 use crate::interpreter::Interpreter;
 use crate::parser::Parser;
+use crate::resolver::Resolver;
 use crate::scanner::Scanner;
+use crate::stmt::Stmt;
+use crate::token::Token;
 use std::fs;
 use std::io::{self, Write};
+use std::time::Instant;
+
+const REPL_HELP: &str = "\
+:load <path>   run a file in the current session
+:reset         forget every global defined in this session
+:env           list the globals this session defined and their values
+:ast <code>    print the parse tree of an expression or statements
+:tokens <code> print the scanner's tokens
+:time <code>   run an expression or statements and report how long it took
+:help          show this message";
+
+const REPL_SOURCE_NAME: &str = "<repl>";
+const DEFAULT_SOURCE_NAME: &str = "<script>";
 
 pub struct App {
     interpreter: Interpreter,
+    diagnostic_format: DiagnosticFormat,
+    /// The name and text of every script run in this session. A span whose
+    /// `source` is `n` points into entry `n - 1`, so an error raised in a
+    /// function defined by an earlier run renders against that run's code.
+    sources: Vec<(String, String)>,
+}
+
+impl Default for App {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl App {
     pub fn new() -> Self {
         App {
             interpreter: Interpreter::new(),
+            diagnostic_format: DiagnosticFormat::default(),
+            sources: Vec::new(),
         }
     }
 
-    pub fn run_file(&mut self, path: &str) -> io::Result<()> {
-        let bytes = fs::read(path)?;
-        let source = String::from_utf8_lossy(&bytes);
-
-        if let Err(err) = self.run_source(&source) {
-            eprintln!("{err}");
-            std::process::exit(65);
-        }
-        Ok(())
+    /// Chooses how errors are rendered. The default is plain text without
+    /// colours so that output captured in logs stays readable.
+    pub fn set_diagnostic_format(&mut self, format: DiagnosticFormat) {
+        self.diagnostic_format = format;
     }
 
     pub fn run_prompt(&mut self) -> io::Result<()> {
@@ -39,32 +65,228 @@ impl App {
             if bytes == 0 {
                 break;
             }
-            if let Err(err) = self.run_source(&line) {
+            let result = match line.trim_start().strip_prefix(':') {
+                Some(command) => self.run_command(command.trim_end()),
+                None => self.run_named(REPL_SOURCE_NAME, &line),
+            };
+            if let Err(err) = result {
                 eprintln!("{err}");
             }
+            if self.exit_code().is_some() {
+                break;
+            }
         }
 
         Ok(())
     }
 
+    /// Handles a REPL meta-command; `command` is the line without its leading `:`.
+    pub fn run_command(&mut self, command: &str) -> Result<(), String> {
+        let (name, argument) = match command.split_once(char::is_whitespace) {
+            Some((name, argument)) => (name, argument.trim()),
+            None => (command, ""),
+        };
+
+        match name {
+            "load" => {
+                if argument.is_empty() {
+                    return Err("Usage: :load <path>".to_string());
+                }
+                let source = fs::read_to_string(argument)
+                    .map_err(|err| format!("Could not read '{argument}': {err}"))?;
+                self.run_named(argument, &source)
+            }
+            "reset" => {
+                self.interpreter.reset();
+                Ok(())
+            }
+            "env" => {
+                for (name, value) in self.interpreter.user_globals() {
+                    println!("{name} = {value}");
+                }
+                Ok(())
+            }
+            "ast" => {
+                let tokens = Self::scan(argument, 0)
+                    .map_err(|errors| self.render(REPL_SOURCE_NAME, argument, &errors))?;
+                if let Ok(expr) = Parser::new(tokens).parse_expression() {
+                    println!("{expr}");
+                    return Ok(());
+                }
+                let ast = Self::dump_ast(argument)
+                    .map_err(|errors| self.render(REPL_SOURCE_NAME, argument, &errors))?;
+                print!("{ast}");
+                Ok(())
+            }
+            "tokens" => {
+                let tokens = Self::dump_tokens(argument)
+                    .map_err(|errors| self.render(REPL_SOURCE_NAME, argument, &errors))?;
+                print!("{tokens}");
+                Ok(())
+            }
+            "time" => {
+                let source_id = self.add_source(REPL_SOURCE_NAME, argument);
+                let tokens = Self::scan(argument, source_id)
+                    .map_err(|errors| self.render(REPL_SOURCE_NAME, argument, &errors))?;
+                let start = Instant::now();
+                if let Ok(expr) = Parser::new(tokens).parse_expression() {
+                    let value = self.interpreter.evaluate(&expr).map_err(|err| {
+                        self.render(REPL_SOURCE_NAME, argument, &[Diagnostic::from(&err)])
+                    })?;
+                    println!("{value}");
+                } else {
+                    self.run_registered(REPL_SOURCE_NAME, argument, source_id)?;
+                }
+                println!("took {:?}", start.elapsed());
+                Ok(())
+            }
+            "help" => {
+                println!("{REPL_HELP}");
+                Ok(())
+            }
+            _ => Err(format!(
+                "Unknown command ':{name}'. Type :help for a list of commands."
+            )),
+        }
+    }
+
     pub fn run_source(&mut self, source: &str) -> Result<(), String> {
-        let mut scanner = Scanner::new(source);
-        let tokens = scanner
-            .scan_tokens()
-            .map_err(|err| format!("Scan error: {err}"))?;
-        let mut parser = Parser::new(tokens);
+        self.run_named(DEFAULT_SOURCE_NAME, source)
+    }
 
-        let statements = parser
-            .parse()
-            .map_err(|err| format!("Parse error: {err:#?}"))?;
+    /// Runs `source`, rendering any errors as diagnostics against `name`.
+    pub fn run_named(&mut self, name: &str, source: &str) -> Result<(), String> {
+        let source_id = self.add_source(name, source);
+        self.run_registered(name, source, source_id)
+    }
+
+    /// Runs a script already added with [`add_source`](Self::add_source).
+    fn run_registered(&mut self, name: &str, source: &str, source_id: usize) -> Result<(), String> {
+        // Warnings are for `check`; a run reports only what stops it.
+        let (statements, _warnings) =
+            Self::check(source, source_id).map_err(|errors| self.render(name, source, &errors))?;
 
         self.interpreter
             .interpret(&statements)
-            .map_err(|err| format!("Runtime error: {:?}", err))?;
-        Ok(())
+            .map_err(|err| self.render(name, source, &[Diagnostic::from(&err)]))
+    }
+
+    /// Renders diagnostics against `name` and `source`, except those whose
+    /// spans point into an earlier run of this session, which render against
+    /// the script they came from.
+    pub fn render(&self, name: &str, source: &str, diagnostics: &[Diagnostic]) -> String {
+        let source_of = |diagnostic: &Diagnostic| {
+            diagnostic
+                .span
+                .and_then(|span| span.source.checked_sub(1))
+                .and_then(|index| self.sources.get(index))
+                .map_or((name, source), |(name, text)| {
+                    (name.as_str(), text.as_str())
+                })
+        };
+        diagnostic::render_each(diagnostics, source_of, self.diagnostic_format)
+    }
+
+    /// Remembers a script run in this session and returns the id its spans
+    /// carry. A script run again under the same name keeps its first id, so
+    /// repeating a REPL line does not store it twice.
+    fn add_source(&mut self, name: &str, source: &str) -> usize {
+        let known = self
+            .sources
+            .iter()
+            .position(|(known_name, known_source)| known_name == name && known_source == source);
+        if let Some(index) = known {
+            return index + 1;
+        }
+        self.sources.push((name.to_string(), source.to_string()));
+        self.sources.len()
+    }
+
+    /// The code a script asked to exit with by calling `exit()`.
+    pub fn exit_code(&self) -> Option<i32> {
+        self.interpreter.exit_code()
     }
 
     pub fn interpreter_mut(&mut self) -> &mut Interpreter {
         &mut self.interpreter
     }
+
+    /// Scans, parses and resolves `source` without running it, returning
+    /// its warnings if there are no errors.
+    pub fn check_source(source: &str) -> Result<Vec<Diagnostic>, Vec<Diagnostic>> {
+        Self::check(source, 0).map(|(_, warnings)| warnings)
+    }
+
+    fn check(
+        source: &str,
+        source_id: usize,
+    ) -> Result<(Vec<Stmt>, Vec<Diagnostic>), Vec<Diagnostic>> {
+        let statements = Self::parse(source, source_id)?;
+        let mut resolver = Resolver::new();
+        resolver
+            .resolve(&statements)
+            .map_err(|errors| errors.iter().map(Diagnostic::from).collect::<Vec<_>>())?;
+        let warnings = resolver
+            .take_warnings()
+            .iter()
+            .map(Diagnostic::from)
+            .collect();
+        Ok((statements, warnings))
+    }
+
+    /// Lists the scanner's tokens, one per line.
+    pub fn dump_tokens(source: &str) -> Result<String, Vec<Diagnostic>> {
+        let mut out = String::new();
+        for token in Self::scan(source, 0)? {
+            out.push_str(&format!(
+                "{:>4}:{:<3} {:?} '{}' {}\n",
+                token.line,
+                token.column,
+                token.token_type,
+                token.lexeme,
+                token
+                    .literal
+                    .as_ref()
+                    .map_or("-".to_string(), |literal| literal.to_string())
+            ));
+        }
+        Ok(out)
+    }
+
+    /// Prints each parsed statement as an s-expression, one per line.
+    pub fn dump_ast(source: &str) -> Result<String, Vec<Diagnostic>> {
+        Ok(Self::parse(source, 0)?
+            .iter()
+            .map(|statement| format!("{statement}\n"))
+            .collect())
+    }
+
+    /// Re-formats `source`, refusing to touch code that does not parse.
+    pub fn format_source(source: &str) -> Result<String, Vec<Diagnostic>> {
+        Self::parse(source, 0)?;
+        let (tokens, _) = Scanner::new(source).keep_comments(true).scan_tokens();
+        Ok(formatter::format_tokens(&tokens))
+    }
+
+    fn scan(source: &str, source_id: usize) -> Result<Vec<Token>, Vec<Diagnostic>> {
+        let (tokens, errors) = Scanner::new(source).source_id(source_id).scan_tokens();
+        if errors.is_empty() {
+            Ok(tokens)
+        } else {
+            Err(errors.iter().map(Diagnostic::from).collect())
+        }
+    }
+
+    /// Parses `source`, reporting lexical and syntax errors together.
+    fn parse(source: &str, source_id: usize) -> Result<Vec<Stmt>, Vec<Diagnostic>> {
+        let (tokens, scan_errors) = Scanner::new(source).source_id(source_id).scan_tokens();
+        let mut diagnostics: Vec<Diagnostic> = scan_errors.iter().map(Diagnostic::from).collect();
+        match Parser::new(tokens).parse() {
+            Ok(statements) if diagnostics.is_empty() => return Ok(statements),
+            Ok(_) => {}
+            Err(errors) => diagnostics.extend(errors.iter().map(Diagnostic::from)),
+        }
+        diagnostics.sort_by_key(|diagnostic| diagnostic.span.map(|span| span.start));
+        Err(diagnostics)
+    }
 }
@@ -0,0 +1,433 @@
+//! Arbitrary-precision integers for results that overflow `i64`.
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, BitAnd, BitOr, BitXor, Mul, Neg, Sub};
+
+/// A signed integer of any size, stored as base-2^32 limbs with the least
+/// significant first.
+///
+/// The representation is kept normalized: there are no leading zero limbs
+/// and zero is never negative, so equal numbers have equal fields and the
+/// derived `Hash` agrees with `Eq`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BigInt {
+    negative: bool,
+    limbs: Vec<u32>,
+}
+
+impl BigInt {
+    fn new(negative: bool, mut limbs: Vec<u32>) -> Self {
+        while limbs.last() == Some(&0) {
+            limbs.pop();
+        }
+        BigInt {
+            negative: negative && !limbs.is_empty(),
+            limbs,
+        }
+    }
+
+    pub fn zero() -> Self {
+        BigInt::new(false, Vec::new())
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    /// The number of bits in the magnitude.
+    pub fn bits(&self) -> u64 {
+        match self.limbs.last() {
+            Some(top) => self.limbs.len() as u64 * 32 - top.leading_zeros() as u64,
+            None => 0,
+        }
+    }
+
+    /// Parses unsigned digits in `radix`, skipping `_` separators.
+    pub fn parse(digits: &str, radix: u32) -> Option<Self> {
+        let mut limbs = Vec::new();
+        let mut seen_digit = false;
+        for c in digits.chars().filter(|c| *c != '_') {
+            mul_add_small(&mut limbs, radix, c.to_digit(radix)?);
+            seen_digit = true;
+        }
+        seen_digit.then(|| BigInt::new(false, limbs))
+    }
+
+    /// Converts a finite, whole float exactly.
+    pub fn from_f64(value: f64) -> Option<Self> {
+        if !value.is_finite() || value.fract() != 0.0 {
+            return None;
+        }
+        let bits = value.to_bits();
+        let exponent = ((bits >> 52) & 0x7ff) as i32;
+        if exponent == 0 {
+            // Zero or subnormal; only zero is whole.
+            return Some(BigInt::zero());
+        }
+        let mantissa = (bits & ((1 << 52) - 1)) | (1 << 52);
+        let shift = exponent - 1075;
+        let magnitude = if shift >= 0 {
+            BigInt::from_u64(mantissa).shl(shift as u32)
+        } else {
+            BigInt::from_u64(mantissa >> -shift)
+        };
+        Some(if value < 0.0 { -&magnitude } else { magnitude })
+    }
+
+    fn from_u64(value: u64) -> Self {
+        BigInt::new(false, vec![value as u32, (value >> 32) as u32])
+    }
+
+    pub fn to_i64(&self) -> Option<i64> {
+        if self.limbs.len() > 2 {
+            return None;
+        }
+        let magnitude = self
+            .limbs
+            .iter()
+            .rev()
+            .fold(0u64, |value, limb| (value << 32) | *limb as u64);
+        if !self.negative {
+            i64::try_from(magnitude).ok()
+        } else if magnitude <= i64::MAX as u64 + 1 {
+            Some((magnitude as i64).wrapping_neg())
+        } else {
+            None
+        }
+    }
+
+    /// The nearest float, or an infinity when out of range.
+    pub fn to_f64(&self) -> f64 {
+        let magnitude = self
+            .limbs
+            .iter()
+            .rev()
+            .fold(0.0, |value, limb| value * 4_294_967_296.0 + *limb as f64);
+        if self.negative { -magnitude } else { magnitude }
+    }
+
+    /// Multiplies by `2^bits`.
+    pub fn shl(&self, bits: u32) -> Self {
+        let (whole, part) = ((bits / 32) as usize, bits % 32);
+        let mut limbs = vec![0; whole];
+        let mut carry = 0;
+        for limb in &self.limbs {
+            let shifted = (*limb as u64) << part;
+            limbs.push(shifted as u32 | carry);
+            carry = (shifted >> 32) as u32;
+        }
+        limbs.push(carry);
+        BigInt::new(self.negative, limbs)
+    }
+
+    /// Divides by `2^bits`, rounding towards negative infinity as `>>` does
+    /// on a two's complement integer.
+    pub fn shr(&self, bits: u32) -> Self {
+        let (quotient, _) = self
+            .div_rem_floor(&BigInt::from(1).shl(bits))
+            .expect("a power of two is not zero");
+        quotient
+    }
+
+    /// The number in two's complement over `length` limbs, which must leave
+    /// room for the sign bit.
+    fn twos_complement(&self, length: usize) -> Vec<u32> {
+        let mut limbs = self.limbs.clone();
+        limbs.resize(length, 0);
+        if self.negative {
+            negate_limbs(&mut limbs);
+        }
+        limbs
+    }
+
+    fn from_twos_complement(mut limbs: Vec<u32>) -> Self {
+        let negative = limbs.last().is_some_and(|top| top >> 31 == 1);
+        if negative {
+            negate_limbs(&mut limbs);
+        }
+        BigInt::new(negative, limbs)
+    }
+
+    /// Applies a bitwise operator limb by limb, treating both numbers as two's
+    /// complement of unlimited width, so negative numbers have infinitely
+    /// many leading ones.
+    fn bitwise(&self, other: &BigInt, operator: impl Fn(u32, u32) -> u32) -> Self {
+        let length = self.limbs.len().max(other.limbs.len()) + 1;
+        let limbs = self
+            .twos_complement(length)
+            .into_iter()
+            .zip(other.twos_complement(length))
+            .map(|(a, b)| operator(a, b))
+            .collect();
+        BigInt::from_twos_complement(limbs)
+    }
+
+    pub fn pow(&self, mut exponent: u32) -> Self {
+        let mut base = self.clone();
+        let mut result = BigInt::from(1);
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = &result * &base;
+            }
+            exponent >>= 1;
+            if exponent > 0 {
+                base = &base * &base;
+            }
+        }
+        result
+    }
+
+    /// Floored division: the quotient rounds towards negative infinity and
+    /// the remainder takes the sign of the divisor, matching `~/` and `%`.
+    /// Returns `None` when dividing by zero.
+    pub fn div_rem_floor(&self, divisor: &BigInt) -> Option<(BigInt, BigInt)> {
+        if divisor.is_zero() {
+            return None;
+        }
+        let (quotient, remainder) = div_rem_magnitude(&self.limbs, &divisor.limbs);
+        let quotient = BigInt::new(self.negative != divisor.negative, quotient);
+        let remainder = BigInt::new(self.negative, remainder);
+        if !remainder.is_zero() && remainder.negative != divisor.negative {
+            Some((&quotient - &BigInt::from(1), &remainder + divisor))
+        } else {
+            Some((quotient, remainder))
+        }
+    }
+}
+
+impl From<i64> for BigInt {
+    fn from(value: i64) -> Self {
+        let magnitude = BigInt::from_u64(value.unsigned_abs());
+        BigInt::new(value < 0, magnitude.limbs)
+    }
+}
+
+impl Add for &BigInt {
+    type Output = BigInt;
+
+    fn add(self, other: &BigInt) -> BigInt {
+        if self.negative == other.negative {
+            return BigInt::new(self.negative, add_magnitude(&self.limbs, &other.limbs));
+        }
+        match compare_magnitude(&self.limbs, &other.limbs) {
+            Ordering::Less => BigInt::new(other.negative, sub_magnitude(&other.limbs, &self.limbs)),
+            _ => BigInt::new(self.negative, sub_magnitude(&self.limbs, &other.limbs)),
+        }
+    }
+}
+
+impl Sub for &BigInt {
+    type Output = BigInt;
+
+    fn sub(self, other: &BigInt) -> BigInt {
+        self + &-other
+    }
+}
+
+impl Mul for &BigInt {
+    type Output = BigInt;
+
+    fn mul(self, other: &BigInt) -> BigInt {
+        BigInt::new(
+            self.negative != other.negative,
+            mul_magnitude(&self.limbs, &other.limbs),
+        )
+    }
+}
+
+impl Neg for &BigInt {
+    type Output = BigInt;
+
+    fn neg(self) -> BigInt {
+        BigInt::new(!self.negative, self.limbs.clone())
+    }
+}
+
+impl BitAnd for &BigInt {
+    type Output = BigInt;
+
+    fn bitand(self, other: &BigInt) -> BigInt {
+        self.bitwise(other, |a, b| a & b)
+    }
+}
+
+impl BitOr for &BigInt {
+    type Output = BigInt;
+
+    fn bitor(self, other: &BigInt) -> BigInt {
+        self.bitwise(other, |a, b| a | b)
+    }
+}
+
+impl BitXor for &BigInt {
+    type Output = BigInt;
+
+    fn bitxor(self, other: &BigInt) -> BigInt {
+        self.bitwise(other, |a, b| a ^ b)
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => compare_magnitude(&self.limbs, &other.limbs),
+            (true, true) => compare_magnitude(&other.limbs, &self.limbs),
+        }
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+        // Peel off nine decimal digits at a time, least significant first.
+        let mut chunks = Vec::new();
+        let mut limbs = self.limbs.clone();
+        while !limbs.is_empty() {
+            let (quotient, remainder) = div_small(&limbs, 1_000_000_000);
+            chunks.push(remainder);
+            limbs = quotient;
+        }
+        if self.negative {
+            write!(f, "-")?;
+        }
+        let mut chunks = chunks.iter().rev();
+        if let Some(first) = chunks.next() {
+            write!(f, "{}", first)?;
+        }
+        for chunk in chunks {
+            write!(f, "{:09}", chunk)?;
+        }
+        Ok(())
+    }
+}
+
+fn trim(mut limbs: Vec<u32>) -> Vec<u32> {
+    while limbs.last() == Some(&0) {
+        limbs.pop();
+    }
+    limbs
+}
+
+fn compare_magnitude(a: &[u32], b: &[u32]) -> Ordering {
+    a.len()
+        .cmp(&b.len())
+        .then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn add_magnitude(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut limbs = Vec::with_capacity(a.len().max(b.len()) + 1);
+    let mut carry = 0u64;
+    for index in 0..a.len().max(b.len()) {
+        let sum = *a.get(index).unwrap_or(&0) as u64 + *b.get(index).unwrap_or(&0) as u64 + carry;
+        limbs.push(sum as u32);
+        carry = sum >> 32;
+    }
+    limbs.push(carry as u32);
+    trim(limbs)
+}
+
+/// `a - b` for magnitudes with `a >= b`.
+fn sub_magnitude(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut limbs = Vec::with_capacity(a.len());
+    let mut borrow = 0i64;
+    for (index, limb) in a.iter().enumerate() {
+        let mut difference = *limb as i64 - *b.get(index).unwrap_or(&0) as i64 - borrow;
+        borrow = 0;
+        if difference < 0 {
+            difference += 1 << 32;
+            borrow = 1;
+        }
+        limbs.push(difference as u32);
+    }
+    trim(limbs)
+}
+
+fn mul_magnitude(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut limbs = vec![0u32; a.len() + b.len()];
+    for (i, x) in a.iter().enumerate() {
+        let mut carry = 0u64;
+        for (j, y) in b.iter().enumerate() {
+            let product = *x as u64 * *y as u64 + limbs[i + j] as u64 + carry;
+            limbs[i + j] = product as u32;
+            carry = product >> 32;
+        }
+        limbs[i + b.len()] = carry as u32;
+    }
+    trim(limbs)
+}
+
+/// Negates limbs in place as a two's complement number: invert and add one.
+fn negate_limbs(limbs: &mut [u32]) {
+    let mut carry = true;
+    for limb in limbs {
+        (*limb, carry) = (!*limb).overflowing_add(carry as u32);
+    }
+}
+
+fn mul_add_small(limbs: &mut Vec<u32>, factor: u32, addend: u32) {
+    let mut carry = addend as u64;
+    for limb in limbs.iter_mut() {
+        let value = *limb as u64 * factor as u64 + carry;
+        *limb = value as u32;
+        carry = value >> 32;
+    }
+    if carry > 0 {
+        limbs.push(carry as u32);
+    }
+}
+
+fn div_small(limbs: &[u32], divisor: u32) -> (Vec<u32>, u32) {
+    let mut quotient = vec![0u32; limbs.len()];
+    let mut remainder = 0u64;
+    for (index, limb) in limbs.iter().enumerate().rev() {
+        let value = (remainder << 32) | *limb as u64;
+        quotient[index] = (value / divisor as u64) as u32;
+        remainder = value % divisor as u64;
+    }
+    (trim(quotient), remainder as u32)
+}
+
+/// Truncating division of magnitudes; `b` must not be zero.
+fn div_rem_magnitude(a: &[u32], b: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    if compare_magnitude(a, b) == Ordering::Less {
+        return (Vec::new(), a.to_vec());
+    }
+    if let [divisor] = b {
+        let (quotient, remainder) = div_small(a, *divisor);
+        return (quotient, trim(vec![remainder]));
+    }
+    // Schoolbook binary long division: bring down one bit at a time.
+    let mut quotient = vec![0u32; a.len()];
+    let mut remainder: Vec<u32> = Vec::new();
+    for bit in (0..a.len() * 32).rev() {
+        let mut carry = (a[bit / 32] >> (bit % 32)) & 1;
+        for limb in remainder.iter_mut() {
+            let next = *limb >> 31;
+            *limb = (*limb << 1) | carry;
+            carry = next;
+        }
+        if carry > 0 {
+            remainder.push(carry);
+        }
+        if compare_magnitude(&remainder, b) != Ordering::Less {
+            remainder = sub_magnitude(&remainder, b);
+            quotient[bit / 32] |= 1 << (bit % 32);
+        }
+    }
+    (trim(quotient), remainder)
+}
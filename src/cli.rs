@@ -0,0 +1,437 @@
+use crate::app::App;
+use crate::diagnostic::{Diagnostic, DiagnosticFormat};
+use crate::interpreter::MAX_CALL_DEPTH;
+use crate::policy::Policy;
+use std::cell::RefCell;
+use std::env;
+use std::fs;
+use std::io::{self, IsTerminal, Read, Write};
+use std::panic;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::thread;
+
+pub const USAGE: &str = "\
+Usage: lox [options] [command] [script | -e <code> | -] [args...]
+
+Commands:
+  run <script>     run a script (the default when a script is given); any
+                   further arguments are passed to it and read with args()
+  repl             start the interactive prompt (the default with no arguments)
+  check <script>   parse and resolve a script without running it
+  tokens <script>  print the scanner's tokens
+  ast <script>     print the parse tree
+  fmt <script>     print the script re-formatted
+  test <path>...   run .lox files and compare their output with `// expect:` comments
+
+A script is a file path, `-e <code>` for inline code, or `-` to read stdin.
+
+Options:
+  --color <when>          colour diagnostics: auto (the default), always or never
+  --error-format <kind>   human (the default) or json, one object per line
+  --host-access <access>  what scripts may touch on this machine: allow (the
+                          default), deny, or a directory to confine files to
+  --seed <n>              run deterministically: seed random() with n and start
+                          clock() at 0, advancing it only by sleep()";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Input {
+    File(String),
+    Inline(String),
+    Stdin,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    /// A script and the arguments passed to it.
+    Run(Input, Vec<String>),
+    Repl,
+    Check(Input),
+    Tokens(Input),
+    Ast(Input),
+    Fmt(Input),
+    Test(Vec<String>),
+    Help,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorChoice {
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Options {
+    pub color: ColorChoice,
+    pub json_errors: bool,
+    pub policy: Policy,
+    /// Set by `--seed` for reproducible runs.
+    pub seed: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cli {
+    pub command: Command,
+    pub options: Options,
+}
+
+impl Options {
+    fn diagnostic_format(&self) -> DiagnosticFormat {
+        if self.json_errors {
+            DiagnosticFormat::Json
+        } else if self.color.enabled() {
+            DiagnosticFormat::Color
+        } else {
+            DiagnosticFormat::Plain
+        }
+    }
+}
+
+impl ColorChoice {
+    /// Auto colours only when stderr is a terminal and `NO_COLOR` is unset.
+    fn enabled(self) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => io::stderr().is_terminal() && env::var_os("NO_COLOR").is_none(),
+        }
+    }
+}
+
+impl Input {
+    fn name(&self) -> &str {
+        match self {
+            Input::File(path) => path,
+            Input::Inline(_) => "<inline>",
+            Input::Stdin => "<stdin>",
+        }
+    }
+
+    fn read(&self) -> io::Result<String> {
+        match self {
+            Input::File(path) => {
+                let bytes = fs::read(path)?;
+                Ok(String::from_utf8_lossy(&bytes).into_owned())
+            }
+            Input::Inline(code) => Ok(code.clone()),
+            Input::Stdin => {
+                let mut source = String::new();
+                io::stdin().read_to_string(&mut source)?;
+                Ok(source)
+            }
+        }
+    }
+}
+
+/// Parses the command line, without the program name. Options come before
+/// the command.
+pub fn parse_args(args: &[String]) -> Result<Cli, String> {
+    let mut options = Options::default();
+    let mut rest = args;
+    while let Some(option) = rest
+        .first()
+        .filter(|option| option.starts_with("--") && *option != "--help")
+    {
+        let (name, value) = match option.split_once('=') {
+            Some((name, value)) => {
+                rest = &rest[1..];
+                (name, value)
+            }
+            None => {
+                let value = rest
+                    .get(1)
+                    .ok_or_else(|| format!("{option} needs a value"))?;
+                rest = &rest[2..];
+                (option.as_str(), value.as_str())
+            }
+        };
+        match (name, value) {
+            ("--color", "auto") => options.color = ColorChoice::Auto,
+            ("--color", "always") => options.color = ColorChoice::Always,
+            ("--color", "never") => options.color = ColorChoice::Never,
+            ("--error-format", "human") => options.json_errors = false,
+            ("--error-format", "json") => options.json_errors = true,
+            ("--host-access", "allow") => options.policy = Policy::Allow,
+            ("--host-access", "deny") => options.policy = Policy::Deny,
+            ("--host-access", "") => return Err(format!("{name} needs a value")),
+            ("--host-access", root) => options.policy = Policy::Sandbox(PathBuf::from(root)),
+            ("--seed", seed) => {
+                let seed = seed
+                    .parse()
+                    .map_err(|_| format!("invalid {name} value '{seed}'"))?;
+                options.seed = Some(seed);
+            }
+            ("--color" | "--error-format", _) => {
+                return Err(format!("invalid {name} value '{value}'"));
+            }
+            _ => return Err(format!("unknown option '{name}'")),
+        }
+    }
+    let command = parse_command(rest)?;
+    Ok(Cli { command, options })
+}
+
+fn parse_command(args: &[String]) -> Result<Command, String> {
+    let Some(first) = args.first() else {
+        return Ok(Command::Repl);
+    };
+    let rest = &args[1..];
+    match first.as_str() {
+        "-h" | "--help" | "help" => Ok(Command::Help),
+        "repl" if rest.is_empty() => Ok(Command::Repl),
+        "repl" => Err("repl takes no arguments".to_string()),
+        "run" => parse_run(rest),
+        "check" => parse_input(rest).map(Command::Check),
+        "tokens" => parse_input(rest).map(Command::Tokens),
+        "ast" => parse_input(rest).map(Command::Ast),
+        "fmt" => parse_input(rest).map(Command::Fmt),
+        "test" if rest.is_empty() => Err("test needs at least one path".to_string()),
+        "test" => Ok(Command::Test(rest.to_vec())),
+        _ => parse_run(args),
+    }
+}
+
+/// Splits a script from the arguments that follow it, which belong to the
+/// script even when they look like options.
+fn parse_run(args: &[String]) -> Result<Command, String> {
+    let input_length = if args.first().is_some_and(|flag| flag == "-e") {
+        2
+    } else {
+        1
+    };
+    let (input, script_args) = args.split_at(input_length.min(args.len()));
+    Ok(Command::Run(parse_input(input)?, script_args.to_vec()))
+}
+
+fn parse_input(args: &[String]) -> Result<Input, String> {
+    match args {
+        [flag, code] if flag == "-e" => Ok(Input::Inline(code.clone())),
+        [flag] if flag == "-e" => Err("-e needs a code argument".to_string()),
+        [dash] if dash == "-" => Ok(Input::Stdin),
+        [path] if !path.starts_with('-') => Ok(Input::File(path.clone())),
+        [option] => Err(format!("unknown option '{option}'")),
+        [] => Err("missing script".to_string()),
+        _ => Err("too many arguments".to_string()),
+    }
+}
+
+/// The stack scripts run on: room for [`MAX_CALL_DEPTH`] nested Lox calls
+/// even in a debug build, where a call made through a native such as
+/// `map()` can take over a hundred kilobytes.
+pub const STACK_SIZE: usize = 256 * 1024 * MAX_CALL_DEPTH;
+
+/// Runs the command line and returns the process exit code. The work is
+/// done on a thread with a [`STACK_SIZE`] stack, as the main thread's is
+/// too small for deep recursion.
+pub fn run(args: &[String]) -> i32 {
+    let args = args.to_vec();
+    thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(move || run_command(&args))
+        .expect("failed to start the interpreter thread")
+        .join()
+        .unwrap_or_else(|payload| panic::resume_unwind(payload))
+}
+
+fn run_command(args: &[String]) -> i32 {
+    let Cli { command, options } = match parse_args(args) {
+        Ok(cli) => cli,
+        Err(err) => {
+            eprintln!("error: {err}\n\n{USAGE}");
+            return 64;
+        }
+    };
+    let mut app = App::new();
+    app.set_diagnostic_format(options.diagnostic_format());
+    app.interpreter_mut().set_policy(options.policy);
+    if let Some(seed) = options.seed {
+        app.interpreter_mut().set_deterministic(seed);
+    }
+
+    match command {
+        Command::Help => {
+            println!("{USAGE}");
+            0
+        }
+        Command::Repl => match app.run_prompt() {
+            Ok(()) => app.exit_code().unwrap_or(0),
+            Err(err) => {
+                eprintln!("{err}");
+                66
+            }
+        },
+        Command::Run(input, script_args) => {
+            app.interpreter_mut().set_args(script_args);
+            let status = with_source(&input, |name, source| app.run_named(name, source));
+            app.exit_code().unwrap_or(status)
+        }
+        Command::Check(input) => with_source(&input, |name, source| {
+            let warnings =
+                App::check_source(source).map_err(|errors| app.render(name, source, &errors))?;
+            if !warnings.is_empty() {
+                eprintln!("{}", app.render(name, source, &warnings));
+            }
+            Ok(())
+        }),
+        Command::Tokens(input) => with_source(&input, |name, source| {
+            checked(&app, name, source, App::dump_tokens(source))
+        }),
+        Command::Ast(input) => with_source(&input, |name, source| {
+            checked(&app, name, source, App::dump_ast(source))
+        }),
+        Command::Fmt(input) => with_source(&input, |name, source| {
+            checked(&app, name, source, App::format_source(source))
+        }),
+        Command::Test(paths) => run_tests(&paths),
+    }
+}
+
+fn with_source(input: &Input, action: impl FnOnce(&str, &str) -> Result<(), String>) -> i32 {
+    let source = match input.read() {
+        Ok(source) => source,
+        Err(err) => {
+            eprintln!("{}: {err}", input.name());
+            return 66;
+        }
+    };
+    match action(input.name(), &source) {
+        Ok(()) => 0,
+        Err(err) => {
+            eprintln!("{err}");
+            65
+        }
+    }
+}
+
+/// Prints the output of a static command or renders its diagnostics.
+fn checked(
+    app: &App,
+    name: &str,
+    source: &str,
+    result: Result<String, Vec<Diagnostic>>,
+) -> Result<(), String> {
+    match result {
+        Ok(output) => {
+            print!("{output}");
+            Ok(())
+        }
+        Err(diagnostics) => Err(app.render(name, source, &diagnostics)),
+    }
+}
+
+/// Collects `print` output so test scripts can be checked against their
+/// `// expect:` comments.
+#[derive(Clone, Default)]
+struct Capture(Rc<RefCell<Vec<u8>>>);
+
+impl Write for Capture {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn run_tests(paths: &[String]) -> i32 {
+    let mut files = Vec::new();
+    for path in paths {
+        if let Err(err) = collect_lox_files(Path::new(path), &mut files) {
+            eprintln!("{path}: {err}");
+            return 66;
+        }
+    }
+
+    let mut failed = 0;
+    for file in &files {
+        match run_test_file(file) {
+            Ok(()) => println!("PASS {}", file.display()),
+            Err(reason) => {
+                failed += 1;
+                println!("FAIL {}\n{}", file.display(), reason);
+            }
+        }
+    }
+    println!("\n{} passed, {} failed", files.len() - failed, failed);
+    if failed == 0 { 0 } else { 1 }
+}
+
+fn collect_lox_files(path: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    if path.is_dir() {
+        let mut entries = fs::read_dir(path)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<io::Result<Vec<_>>>()?;
+        entries.sort();
+        for entry in entries {
+            if entry.is_dir() || entry.extension().is_some_and(|ext| ext == "lox") {
+                collect_lox_files(&entry, files)?;
+            }
+        }
+    } else {
+        fs::metadata(path)?;
+        files.push(path.to_path_buf());
+    }
+    Ok(())
+}
+
+/// Runs one test script. Each `// expect: text` comment adds an expected
+/// output line and `// expect error: text` expects the run to fail with an
+/// error mentioning `text`.
+fn run_test_file(path: &Path) -> Result<(), String> {
+    let source = fs::read_to_string(path).map_err(|err| format!("  {err}"))?;
+    let mut expected_output = Vec::new();
+    let mut expected_error = None;
+    for line in source.lines() {
+        if let Some((_, expectation)) = line.split_once("// expect error: ") {
+            expected_error = Some(expectation.trim().to_string());
+        } else if let Some((_, expectation)) = line.split_once("// expect: ") {
+            expected_output.push(expectation.to_string());
+        }
+    }
+
+    let capture = Capture::default();
+    let mut app = App::new();
+    app.interpreter_mut().set_output(Box::new(capture.clone()));
+    // Expected output is fixed text, so runs must not depend on the time.
+    app.interpreter_mut().set_deterministic(0);
+    let result = app.run_named(&path.display().to_string(), &source);
+
+    let output = String::from_utf8_lossy(&capture.0.borrow()).into_owned();
+    let actual_output: Vec<&str> = output.lines().collect();
+    let mut problems = Vec::new();
+    if actual_output != expected_output {
+        problems.push(format!(
+            "  expected output:\n{}\n  actual output:\n{}",
+            indent(&expected_output.join("\n")),
+            indent(&output)
+        ));
+    }
+    match (result, expected_error) {
+        (Ok(()), Some(expected)) => {
+            problems.push(format!("  expected an error mentioning '{expected}'"))
+        }
+        (Err(err), None) => problems.push(format!("  unexpected error:\n{}", indent(&err))),
+        (Err(err), Some(expected)) if !err.contains(&expected) => problems.push(format!(
+            "  expected an error mentioning '{expected}', got:\n{}",
+            indent(&err)
+        )),
+        _ => {}
+    }
+
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(problems.join("\n"))
+    }
+}
+
+fn indent(text: &str) -> String {
+    text.lines()
+        .map(|line| format!("    {line}"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -0,0 +1,214 @@
+//! Diagnostics for scan, parse, resolve and runtime errors.
+//!
+//! Every diagnostic carries a stable code that tools can match on:
+//!
+//! - `E00xx`: lexical errors
+//! - `E01xx`: syntax errors
+//! - `E02xx`: resolution errors
+//! - `E03xx`: runtime errors
+
+use crate::json;
+use crate::token::Span;
+use std::fmt::Write;
+
+const RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[1;33m";
+const BLUE: &str = "\x1b[1;34m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        }
+    }
+}
+
+/// A problem found in a script, ready to be rendered against its source.
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: &'static str,
+    pub message: String,
+    pub span: Option<Span>,
+}
+
+/// A 1-based line and character column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Position {
+    /// Converts a byte offset into a line and column of `source`.
+    pub fn of(source: &str, offset: usize) -> Self {
+        let offset = floor_char_boundary(source, offset);
+        let before = &source[..offset];
+        let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+        Position {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+        }
+    }
+}
+
+impl Diagnostic {
+    pub fn error(code: &'static str, message: impl Into<String>, span: Option<Span>) -> Self {
+        Diagnostic {
+            severity: Severity::Error,
+            code,
+            message: message.into(),
+            span,
+        }
+    }
+
+    pub fn warning(code: &'static str, message: impl Into<String>, span: Option<Span>) -> Self {
+        Diagnostic {
+            severity: Severity::Warning,
+            ..Diagnostic::error(code, message, span)
+        }
+    }
+
+    /// Renders the diagnostic in the usual compiler layout: the message, the
+    /// file position, the offending source line and a caret underline.
+    pub fn render(&self, file: &str, source: &str, color: bool) -> String {
+        let paint = |style: &'static str| if color { style } else { "" };
+        let reset = paint(RESET);
+        let accent = paint(match self.severity {
+            Severity::Error => RED,
+            Severity::Warning => YELLOW,
+        });
+        let gutter = paint(BLUE);
+
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "{accent}{}[{}]{reset}{}: {}{reset}",
+            self.severity.as_str(),
+            self.code,
+            paint(BOLD),
+            self.message
+        );
+
+        let Some(span) = self.span else {
+            let _ = write!(out, "{gutter}  -->{reset} {file}");
+            return out;
+        };
+        let start = Position::of(source, span.start);
+        let line_text = source.lines().nth(start.line - 1).unwrap_or("");
+        let line_length = line_text.chars().count();
+        let span_length = source
+            .get(floor_char_boundary(source, span.start)..floor_char_boundary(source, span.end))
+            .map_or(0, |text| text.chars().count());
+        let underline = span_length
+            .min(line_length.saturating_sub(start.column - 1))
+            .max(1);
+        let width = start.line.to_string().len();
+
+        let _ = writeln!(
+            out,
+            "{gutter}{:width$}-->{reset} {file}:{}:{}",
+            "", start.line, start.column
+        );
+        let _ = writeln!(out, "{gutter}{:width$} |{reset}", "");
+        let _ = writeln!(out, "{gutter}{} |{reset} {line_text}", start.line);
+        let _ = write!(
+            out,
+            "{gutter}{:width$} |{reset} {:pad$}{accent}{}{reset}",
+            "",
+            "",
+            "^".repeat(underline),
+            pad = start.column - 1
+        );
+        out
+    }
+
+    /// Serialises the diagnostic as a single-line JSON object. Positions are
+    /// 1-based and `end` points just past the last character; both are `null`
+    /// when the diagnostic has no location.
+    pub fn to_json(&self, file: &str, source: &str) -> String {
+        let position = |offset| {
+            let Position { line, column } = Position::of(source, offset);
+            format!("{{\"line\":{line},\"column\":{column}}}")
+        };
+        let (start, end) = match self.span {
+            Some(span) => (position(span.start), position(span.end)),
+            None => ("null".to_string(), "null".to_string()),
+        };
+        format!(
+            "{{\"severity\":{},\"code\":{},\"message\":{},\"file\":{},\"start\":{},\"end\":{}}}",
+            json::quote(self.severity.as_str()),
+            json::quote(self.code),
+            json::quote(&self.message),
+            json::quote(file),
+            start,
+            end
+        )
+    }
+}
+
+fn floor_char_boundary(source: &str, offset: usize) -> usize {
+    let mut offset = offset.min(source.len());
+    while !source.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    offset
+}
+
+/// How diagnostics are written out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DiagnosticFormat {
+    /// Compiler-style text without ANSI colours.
+    #[default]
+    Plain,
+    /// Compiler-style text with ANSI colours.
+    Color,
+    /// One JSON object per line.
+    Json,
+}
+
+/// Renders several diagnostics: human formats are separated by blank lines,
+/// JSON by newlines.
+pub fn render_all(
+    diagnostics: &[Diagnostic],
+    file: &str,
+    source: &str,
+    format: DiagnosticFormat,
+) -> String {
+    render_each(diagnostics, |_| (file, source), format)
+}
+
+/// Like [`render_all`], but renders each diagnostic against the file name
+/// and source `source_of` picks for it, for diagnostics from several scripts.
+pub fn render_each<'a>(
+    diagnostics: &[Diagnostic],
+    source_of: impl Fn(&Diagnostic) -> (&'a str, &'a str),
+    format: DiagnosticFormat,
+) -> String {
+    let render = |diagnostic: &Diagnostic| {
+        let (file, source) = source_of(diagnostic);
+        match format {
+            DiagnosticFormat::Plain => diagnostic.render(file, source, false),
+            DiagnosticFormat::Color => diagnostic.render(file, source, true),
+            DiagnosticFormat::Json => diagnostic.to_json(file, source),
+        }
+    };
+    let separator = match format {
+        DiagnosticFormat::Plain | DiagnosticFormat::Color => "\n\n",
+        DiagnosticFormat::Json => "\n",
+    };
+    diagnostics
+        .iter()
+        .map(render)
+        .collect::<Vec<_>>()
+        .join(separator)
+}
@@ -8,6 +8,12 @@ pub struct Environment {
     enclosing: Option<Rc<RefCell<Environment>>>,
 }
 
+impl Default for Environment {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Environment {
     pub fn new() -> Self {
         Environment {
@@ -27,6 +33,11 @@ impl Environment {
         self.values.insert(name, value);
     }
 
+    /// Iterates over the bindings defined directly in this scope.
+    pub fn values(&self) -> impl Iterator<Item = (&String, &Value)> {
+        self.values.iter()
+    }
+
     pub fn get(&self, name: &str) -> Option<Value> {
         if let Some(value) = self.values.get(name) {
             Some(value.clone())
@@ -1,4 +1,7 @@
+use crate::stmt::{self, Stmt};
 use crate::token::Token;
+use std::fmt;
+use std::rc::Rc;
 
 #[derive(Debug)]
 pub enum Expr {
@@ -18,10 +21,37 @@ pub enum Expr {
         operator: Token,
         right: Box<Expr>,
     },
+    /// `condition ? then_branch : else_branch`; only the chosen branch is
+    /// evaluated.
+    Conditional {
+        condition: Box<Expr>,
+        question: Token,
+        then_branch: Box<Expr>,
+        else_branch: Box<Expr>,
+    },
+    /// `left ?? right`: `right` is evaluated only when `left` is nil.
+    Coalesce {
+        left: Box<Expr>,
+        operator: Token,
+        right: Box<Expr>,
+    },
     Assign {
         name: Token,
         value: Box<Expr>,
     },
+    /// `target op= value`; the target's own sub-expressions are evaluated once.
+    CompoundAssign {
+        target: Box<Expr>,
+        operator: Token,
+        value: Box<Expr>,
+    },
+    /// `++target`, `--target`, `target++` or `target--`. Prefix forms produce
+    /// the updated value, postfix forms the previous one.
+    Increment {
+        target: Box<Expr>,
+        operator: Token,
+        prefix: bool,
+    },
     Unary {
         operator: Token,
         right: Box<Expr>,
@@ -34,57 +64,139 @@ pub enum Expr {
         paren: Token,
         arguments: Vec<Expr>,
     },
+    /// A list literal, `[a, b, c]`.
+    List {
+        bracket: Token,
+        elements: Vec<Expr>,
+    },
+    /// `object[index]`; `bracket` is the opening `[`.
+    Index {
+        object: Box<Expr>,
+        bracket: Token,
+        index: Box<Expr>,
+    },
+    /// `object[index] = value`.
+    IndexAssign {
+        object: Box<Expr>,
+        bracket: Token,
+        index: Box<Expr>,
+        value: Box<Expr>,
+    },
+    /// A map literal, `{"a": 1, "b": 2}`; `brace` is the opening `{`.
+    Map {
+        brace: Token,
+        entries: Vec<(Expr, Expr)>,
+    },
+    /// `object.name`, reading a map entry.
+    Get {
+        object: Box<Expr>,
+        name: Token,
+    },
+    /// `object.name = value`.
+    Set {
+        object: Box<Expr>,
+        name: Token,
+        value: Box<Expr>,
+    },
+    /// An interpolated string: literal segments alternating with embedded
+    /// expressions, concatenated at runtime.
+    Interpolation {
+        parts: Vec<Expr>,
+    },
+    /// An anonymous function, written `fun (a) { ... }` or `(a) => expr`.
+    /// The arrow form's body is a single `return` statement.
+    Function {
+        keyword: Token,
+        params: Vec<Token>,
+        body: Rc<Vec<Stmt>>,
+    },
 }
 
-impl Expr {
-    pub fn to_string(&self) -> String {
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Expr::Binary {
                 left,
                 operator,
                 right,
-            } => {
-                format!(
-                    "({} {} {})",
-                    operator.lexeme,
-                    left.to_string(),
-                    right.to_string()
-                )
-            }
-            Expr::Grouping { expression } => {
-                format!("(group {})", expression.to_string())
-            }
-            Expr::Literal { value } => value.to_string(),
+            } => write!(f, "({} {} {})", operator.lexeme, left, right),
+            Expr::Grouping { expression } => write!(f, "(group {})", expression),
+            Expr::Literal { value } => write!(f, "{}", value),
             Expr::Logical {
                 left,
                 operator,
                 right,
+            } => write!(f, "({} {} {})", operator.lexeme, left, right),
+            Expr::Conditional {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => write!(f, "(?: {} {} {})", condition, then_branch, else_branch),
+            Expr::Coalesce { left, right, .. } => write!(f, "(?? {} {})", left, right),
+            Expr::Assign { name, value } => write!(f, "(assign {} {})", name.lexeme, value),
+            Expr::CompoundAssign {
+                target,
+                operator,
+                value,
+            } => write!(f, "({} {} {})", operator.lexeme, target, value),
+            Expr::Increment {
+                target,
+                operator,
+                prefix,
             } => {
-                format!(
-                    "({} {} {})",
-                    operator.lexeme,
-                    left.to_string(),
-                    right.to_string()
-                )
-            }
-            Expr::Assign { name, value } => {
-                format!("(assign {} {})", name.lexeme, value.to_string())
-            }
-            Expr::Unary { operator, right } => {
-                format!("({} {})", operator.lexeme, right.to_string())
+                let position = if *prefix { "pre" } else { "post" };
+                write!(f, "({}{} {})", position, operator.lexeme, target)
             }
-            Expr::Variable { name } => name.lexeme.clone(),
+            Expr::Unary { operator, right } => write!(f, "({} {})", operator.lexeme, right),
+            Expr::Variable { name } => write!(f, "{}", name.lexeme),
             Expr::Call {
-                callee,
-                paren,
-                arguments,
+                callee, arguments, ..
             } => {
-                let args = arguments
-                    .iter()
-                    .map(|arg| arg.to_string())
-                    .collect::<Vec<_>>()
-                    .join(" ");
-                format!("(call {} {})", callee.to_string(), args)
+                write!(f, "(call {}", callee)?;
+                for argument in arguments {
+                    write!(f, " {}", argument)?;
+                }
+                write!(f, ")")
+            }
+            Expr::List { elements, .. } => {
+                write!(f, "(list")?;
+                for element in elements {
+                    write!(f, " {}", element)?;
+                }
+                write!(f, ")")
+            }
+            Expr::Index { object, index, .. } => write!(f, "(index {} {})", object, index),
+            Expr::IndexAssign {
+                object,
+                index,
+                value,
+                ..
+            } => write!(f, "(index-assign {} {} {})", object, index, value),
+            Expr::Map { entries, .. } => {
+                write!(f, "(map")?;
+                for (key, value) in entries {
+                    write!(f, " {} {}", key, value)?;
+                }
+                write!(f, ")")
+            }
+            Expr::Get { object, name } => write!(f, "(get {} {})", object, name.lexeme),
+            Expr::Set {
+                object,
+                name,
+                value,
+            } => write!(f, "(set {} {} {})", object, name.lexeme, value),
+            Expr::Interpolation { parts } => {
+                write!(f, "(interpolate")?;
+                for part in parts {
+                    write!(f, " {}", part)?;
+                }
+                write!(f, ")")
+            }
+            Expr::Function { params, body, .. } => {
+                write!(f, "(fun ")?;
+                stmt::write_function(f, params, body)?;
+                write!(f, ")")
             }
         }
     }
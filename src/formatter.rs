@@ -0,0 +1,214 @@
+use crate::token::Token;
+use crate::token_type::TokenType;
+
+const INDENT: &str = "    ";
+
+enum Separator {
+    Nothing,
+    Space,
+    Newline,
+}
+
+/// The brackets the formatter is inside of. A `{` opens a block, which is
+/// broken over indented lines, unless it starts a map literal, which stays
+/// on one line.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Group {
+    Paren,
+    Block,
+    Map,
+}
+
+/// Re-indents and re-spaces a token stream.
+///
+/// The formatter works on tokens rather than the AST so that comments and
+/// `for` loops survive untouched; scan with `Scanner::keep_comments(true)`.
+/// A single blank line between statements is preserved.
+pub fn format_tokens(tokens: &[Token]) -> String {
+    let mut out = String::new();
+    let mut depth = 0;
+    let mut nesting = Vec::new();
+    let mut before_previous: Option<&Token> = None;
+    let mut previous: Option<&Token> = None;
+    let mut previous_closed_map = false;
+
+    for token in tokens {
+        if token.token_type == TokenType::EOF {
+            break;
+        }
+        let closes_map =
+            token.token_type == TokenType::RightBrace && nesting.last() == Some(&Group::Map);
+        if token.token_type == TokenType::RightBrace && !closes_map {
+            depth = usize::saturating_sub(depth, 1);
+        }
+
+        let context = Context {
+            before_previous,
+            previous_closed_map,
+            innermost: nesting.last().copied(),
+        };
+        match separator(previous, token, &context) {
+            Separator::Nothing => {}
+            Separator::Space => out.push(' '),
+            Separator::Newline => {
+                out.push('\n');
+                if let Some(previous) = previous
+                    && token.line > end_line(previous) + 1
+                    && previous.token_type != TokenType::LeftBrace
+                    && token.token_type != TokenType::RightBrace
+                {
+                    out.push('\n');
+                }
+                out.push_str(&INDENT.repeat(depth));
+            }
+        }
+        out.push_str(token.lexeme.trim_end());
+
+        match token.token_type {
+            TokenType::LeftParen => nesting.push(Group::Paren),
+            TokenType::LeftBrace if opens_block(previous, previous_closed_map) => {
+                nesting.push(Group::Block);
+                depth += 1;
+            }
+            TokenType::LeftBrace => nesting.push(Group::Map),
+            TokenType::RightParen | TokenType::RightBrace => {
+                nesting.pop();
+            }
+            _ => {}
+        }
+        before_previous = previous;
+        previous = Some(token);
+        previous_closed_map = closes_map;
+    }
+
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    out
+}
+
+/// What the formatter knows about the tokens around the current one.
+struct Context<'a> {
+    before_previous: Option<&'a Token>,
+    /// The previous token is a `}` ending a map literal, which behaves like
+    /// any other operand rather than the end of a block.
+    previous_closed_map: bool,
+    innermost: Option<Group>,
+}
+
+fn separator(previous: Option<&Token>, current: &Token, context: &Context) -> Separator {
+    let Some(previous) = previous else {
+        return Separator::Nothing;
+    };
+    let in_map = context.innermost == Some(Group::Map);
+    if current.token_type == TokenType::Comment {
+        return if current.line == previous.line {
+            Separator::Space
+        } else {
+            Separator::Newline
+        };
+    }
+
+    match (previous.token_type, current.token_type) {
+        // Code can follow a block comment on the same line; not a line comment.
+        (TokenType::Comment, _)
+            if previous.lexeme.starts_with("/*") && current.line == end_line(previous) =>
+        {
+            Separator::Space
+        }
+        (TokenType::Comment, _) => Separator::Newline,
+        // Expressions embedded in a string hug its `${` and `}`.
+        (TokenType::Interpolation, _) => Separator::Nothing,
+        (_, TokenType::String | TokenType::Interpolation) if current.lexeme.starts_with('}') => {
+            Separator::Nothing
+        }
+        (TokenType::LeftBrace, _) | (_, TokenType::RightBrace) if in_map => Separator::Nothing,
+        (_, TokenType::Colon) if in_map => Separator::Nothing,
+        (TokenType::LeftBrace, _) | (_, TokenType::RightBrace) => Separator::Newline,
+        (TokenType::RightBrace, TokenType::Else) if !context.previous_closed_map => {
+            Separator::Space
+        }
+        (
+            TokenType::RightBrace,
+            TokenType::Semicolon | TokenType::RightParen | TokenType::Comma,
+        ) => Separator::Nothing,
+        (TokenType::RightBrace, _) if !context.previous_closed_map => Separator::Newline,
+        (TokenType::Semicolon, _) => {
+            if context.innermost == Some(Group::Paren) {
+                Separator::Space
+            } else {
+                Separator::Newline
+            }
+        }
+        (
+            _,
+            TokenType::Semicolon
+            | TokenType::Comma
+            | TokenType::RightParen
+            | TokenType::RightBracket
+            | TokenType::Dot,
+        ) => Separator::Nothing,
+        (TokenType::LeftParen | TokenType::LeftBracket | TokenType::Dot, _) => Separator::Nothing,
+        (_, TokenType::LeftParen | TokenType::LeftBracket)
+            if ends_operand(previous) || context.previous_closed_map =>
+        {
+            Separator::Nothing
+        }
+        // `- -x` must not run together into `--x`.
+        (TokenType::Minus, TokenType::Minus | TokenType::MinusMinus) => Separator::Space,
+        (_, TokenType::PlusPlus | TokenType::MinusMinus) if ends_operand(previous) => {
+            Separator::Nothing
+        }
+        (
+            TokenType::Bang
+            | TokenType::Minus
+            | TokenType::Tilde
+            | TokenType::PlusPlus
+            | TokenType::MinusMinus,
+            _,
+        ) if !context.before_previous.is_some_and(ends_operand) => Separator::Nothing,
+        _ => Separator::Space,
+    }
+}
+
+/// Whether a `{` after `previous` opens a block rather than a map literal:
+/// blocks start statements and function bodies, maps appear where an
+/// operand is expected.
+fn opens_block(previous: Option<&Token>, previous_closed_map: bool) -> bool {
+    let Some(previous) = previous else {
+        return true;
+    };
+    match previous.token_type {
+        TokenType::RightBrace => !previous_closed_map,
+        TokenType::RightParen
+        | TokenType::Else
+        | TokenType::Semicolon
+        | TokenType::LeftBrace
+        | TokenType::Arrow
+        | TokenType::Comment => true,
+        _ => false,
+    }
+}
+
+/// The line a token ends on; strings and block comments can span lines.
+fn end_line(token: &Token) -> usize {
+    token.line + token.lexeme.matches('\n').count()
+}
+
+/// Whether `token` can end an operand, which makes a following `(` a call and
+/// a following `-` binary.
+fn ends_operand(token: &Token) -> bool {
+    matches!(
+        token.token_type,
+        TokenType::Identifier
+            | TokenType::Number
+            | TokenType::String
+            | TokenType::RightParen
+            | TokenType::RightBracket
+            | TokenType::True
+            | TokenType::False
+            | TokenType::Nil
+            | TokenType::This
+            | TokenType::Super
+    )
+}
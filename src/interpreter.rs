@@ -1,41 +1,406 @@
+use crate::bigint::BigInt;
+use crate::diagnostic::Diagnostic;
 use crate::environment::Environment;
 use crate::expr::Expr;
-use crate::lox_callable::NativeClock;
+use crate::lox_function::LoxFunction;
+use crate::lox_map::LoxMap;
+use crate::policy::Policy;
+use crate::random::Random;
+use crate::stdlib::{self, NativeModule};
 use crate::stmt::Stmt;
-use crate::token::Literal;
+use crate::time_source::{SystemClock, TimeSource, VirtualClock};
+use crate::token::{Literal, Span, Token};
 use crate::token_type::TokenType;
 use crate::value::Value;
 use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, BufRead, Read, Write};
 use std::rc::Rc;
 
 #[derive(Debug)]
-pub enum RuntimeError {
+pub enum RuntimeErrorKind {
     TypeMismatch(String),
     ZeroDivision,
     UndefinedVariable(String),
+    Io(String),
+    Overflow,
+    /// An argument of the right type whose value a function cannot use.
+    InvalidArgument(String),
+    IndexOutOfRange {
+        index: i64,
+        length: usize,
+    },
+    /// Host access refused by the interpreter's [`Policy`].
+    PermissionDenied(String),
+    UndefinedProperty(String),
+    /// Calls nested deeper than [`MAX_CALL_DEPTH`].
+    StackOverflow,
+    /// Raised by `exit(code)` to unwind the whole script; [`Interpreter::interpret`]
+    /// stops there and records the code instead of reporting an error.
+    Exit(i32),
+}
+
+/// A runtime error and, when known, the source location that raised it.
+///
+/// Native functions have no location of their own; the interpreter attaches
+/// the call site to their errors as they propagate.
+#[derive(Debug)]
+pub struct RuntimeError {
+    pub kind: RuntimeErrorKind,
+    pub span: Option<Span>,
+}
+
+impl RuntimeError {
+    pub fn new(kind: RuntimeErrorKind, token: &Token) -> Self {
+        RuntimeError {
+            kind,
+            span: Some(token.span),
+        }
+    }
+
+    /// Points the error at `token` unless it already has a location.
+    pub fn or_at(mut self, token: &Token) -> Self {
+        self.span.get_or_insert(token.span);
+        self
+    }
+}
+
+impl From<RuntimeErrorKind> for RuntimeError {
+    fn from(kind: RuntimeErrorKind) -> Self {
+        RuntimeError { kind, span: None }
+    }
+}
+
+impl RuntimeErrorKind {
+    /// The stable diagnostic code for this kind of error.
+    pub fn code(&self) -> &'static str {
+        match self {
+            RuntimeErrorKind::TypeMismatch(_) => "E0300",
+            RuntimeErrorKind::ZeroDivision => "E0301",
+            RuntimeErrorKind::UndefinedVariable(_) => "E0302",
+            RuntimeErrorKind::Io(_) => "E0303",
+            RuntimeErrorKind::Overflow => "E0304",
+            RuntimeErrorKind::InvalidArgument(_) => "E0305",
+            RuntimeErrorKind::IndexOutOfRange { .. } => "E0306",
+            RuntimeErrorKind::PermissionDenied(_) => "E0307",
+            RuntimeErrorKind::Exit(_) => "E0308",
+            RuntimeErrorKind::UndefinedProperty(_) => "E0309",
+            RuntimeErrorKind::StackOverflow => "E0310",
+        }
+    }
+}
+
+impl fmt::Display for RuntimeErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuntimeErrorKind::TypeMismatch(message) => write!(f, "{}", message),
+            RuntimeErrorKind::ZeroDivision => write!(f, "Division by zero."),
+            RuntimeErrorKind::UndefinedVariable(name) => {
+                write!(f, "Undefined variable '{}'.", name)
+            }
+            RuntimeErrorKind::Io(message) => write!(f, "I/O error: {}", message),
+            RuntimeErrorKind::Overflow => write!(f, "Integer overflow."),
+            RuntimeErrorKind::InvalidArgument(message) => write!(f, "{}", message),
+            RuntimeErrorKind::IndexOutOfRange { index, length } => {
+                write!(f, "Index {} is out of range for length {}.", index, length)
+            }
+            RuntimeErrorKind::PermissionDenied(message) => {
+                write!(f, "Permission denied: {}", message)
+            }
+            RuntimeErrorKind::Exit(code) => write!(f, "Exit with code {}.", code),
+            RuntimeErrorKind::UndefinedProperty(name) => {
+                write!(f, "Undefined property '{}'.", name)
+            }
+            RuntimeErrorKind::StackOverflow => write!(f, "Stack overflow."),
+        }
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.kind)
+    }
+}
+
+impl From<&RuntimeError> for Diagnostic {
+    fn from(err: &RuntimeError) -> Self {
+        Diagnostic::error(err.kind.code(), err.kind.to_string(), err.span)
+    }
+}
+
+/// How deeply Lox calls may nest. Going deeper is a runtime error rather
+/// than a crash, provided the thread running the interpreter has room for
+/// this many calls; the command line gives it [`cli::STACK_SIZE`].
+///
+/// [`cli::STACK_SIZE`]: crate::cli::STACK_SIZE
+pub const MAX_CALL_DEPTH: usize = 1000;
+
+/// Why execution of a statement stopped early: an error, or a `return`
+/// carrying its value up to the enclosing call.
+enum Unwind {
+    Error(RuntimeError),
+    Return(Value),
+}
+
+impl From<RuntimeError> for Unwind {
+    fn from(err: RuntimeError) -> Self {
+        Unwind::Error(err)
+    }
 }
 
 pub struct Interpreter {
+    globals: Rc<RefCell<Environment>>,
+    /// The natives and constants the globals started with, so listings can
+    /// leave out whatever the script has not redefined.
+    builtins: HashMap<String, Value>,
     environment: Rc<RefCell<Environment>>,
+    output: Box<dyn Write>,
+    policy: Policy,
+    args: Vec<String>,
+    /// Where `readLine()` and `readAll()` read from; stdin when `None`.
+    input: Option<Box<dyn BufRead>>,
+    exit_code: Option<i32>,
+    time_source: Box<dyn TimeSource>,
+    random: Random,
+    call_depth: usize,
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Interpreter {
     pub fn new() -> Self {
-        let environment = Rc::new(RefCell::new(Environment::new()));
+        let globals = Self::new_globals();
+        Interpreter {
+            environment: globals.clone(),
+            builtins: Self::bindings(&globals),
+            globals,
+            output: Box::new(io::stdout()),
+            policy: Policy::default(),
+            args: Vec::new(),
+            input: None,
+            exit_code: None,
+            time_source: Box::new(SystemClock),
+            random: Random::from_entropy(),
+            call_depth: 0,
+        }
+    }
+
+    /// Redirects the output of `print` statements, which go to stdout by default.
+    pub fn set_output(&mut self, output: Box<dyn Write>) {
+        self.output = output;
+    }
+
+    /// Restricts what scripts may do to the host; everything is allowed by
+    /// default.
+    pub fn set_policy(&mut self, policy: Policy) {
+        self.policy = policy;
+    }
+
+    pub fn policy(&self) -> &Policy {
+        &self.policy
+    }
+
+    /// Replaces the real clock behind `clock()` and `sleep()`.
+    pub fn set_time_source(&mut self, time_source: Box<dyn TimeSource>) {
+        self.time_source = time_source;
+    }
+
+    pub fn time_source(&self) -> &dyn TimeSource {
+        self.time_source.as_ref()
+    }
+
+    /// Restarts `random()` and `randomInt()` from `seed`.
+    pub fn seed_random(&mut self, seed: u64) {
+        self.random = Random::new(seed);
+    }
+
+    pub fn random(&mut self) -> &mut Random {
+        &mut self.random
+    }
+
+    /// Makes runs reproducible: the clock starts at zero and moves only when
+    /// the script sleeps, and random numbers follow `seed`.
+    pub fn set_deterministic(&mut self, seed: u64) {
+        self.set_time_source(Box::new(VirtualClock::new(0.0)));
+        self.seed_random(seed);
+    }
+
+    /// Sets the command-line arguments scripts read with `args()`.
+    pub fn set_args(&mut self, args: Vec<String>) {
+        self.args = args;
+    }
+
+    pub fn args(&self) -> &[String] {
+        &self.args
+    }
+
+    /// The code passed to `exit()`, once a script has called it.
+    pub fn exit_code(&self) -> Option<i32> {
+        self.exit_code
+    }
+
+    /// Replaces stdin as the source of `readLine()` and `readAll()`.
+    pub fn set_input(&mut self, input: Box<dyn BufRead>) {
+        self.input = Some(input);
+    }
+
+    /// Reads one line of input without its line ending, or `None` at the end
+    /// of the input.
+    pub(crate) fn read_line(&mut self) -> io::Result<Option<String>> {
+        let mut line = String::new();
+        let read = match &mut self.input {
+            Some(input) => input.read_line(&mut line)?,
+            None => io::stdin().lock().read_line(&mut line)?,
+        };
+        if read == 0 {
+            return Ok(None);
+        }
+        if line.ends_with('\n') {
+            line.pop();
+            if line.ends_with('\r') {
+                line.pop();
+            }
+        }
+        Ok(Some(line))
+    }
+
+    /// Reads everything left in the input.
+    pub(crate) fn read_all(&mut self) -> io::Result<String> {
+        let mut text = String::new();
+        match &mut self.input {
+            Some(input) => input.read_to_string(&mut text)?,
+            None => io::stdin().lock().read_to_string(&mut text)?,
+        };
+        Ok(text)
+    }
+
+    fn new_globals() -> Rc<RefCell<Environment>> {
+        let globals = Rc::new(RefCell::new(Environment::new()));
+        for module in stdlib::modules() {
+            module.register(&mut globals.borrow_mut());
+        }
+        globals
+    }
+
+    fn bindings(environment: &Rc<RefCell<Environment>>) -> HashMap<String, Value> {
         environment
-            .borrow_mut()
-            .define("clock".to_string(), Value::Callable(Rc::new(NativeClock)));
-        Interpreter { environment }
+            .borrow()
+            .values()
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect()
+    }
+
+    /// Defines the members of a native module as globals.
+    pub fn register(&mut self, module: &NativeModule) {
+        let members = Rc::new(RefCell::new(Environment::new()));
+        module.register(&mut members.borrow_mut());
+        module.register(&mut self.globals.borrow_mut());
+        self.builtins.extend(Self::bindings(&members));
+    }
+
+    /// Drops every user-defined global, leaving only the builtins.
+    pub fn reset(&mut self) {
+        self.globals = Self::new_globals();
+        self.environment = self.globals.clone();
+        self.builtins = Self::bindings(&self.globals);
+    }
+
+    /// Returns the global bindings sorted by name.
+    pub fn globals(&self) -> Vec<(String, Value)> {
+        let mut globals: Vec<(String, Value)> = Self::bindings(&self.globals).into_iter().collect();
+        globals.sort_by(|a, b| a.0.cmp(&b.0));
+        globals
+    }
+
+    /// Like [`globals`](Self::globals), but without the builtins the script
+    /// has left as they were.
+    pub fn user_globals(&self) -> Vec<(String, Value)> {
+        self.globals()
+            .into_iter()
+            .filter(|(name, value)| self.builtins.get(name) != Some(value))
+            .collect()
     }
 
     pub fn interpret(&mut self, statements: &[Stmt]) -> Result<(), RuntimeError> {
         for statement in statements {
-            self.execute(statement)?;
+            match self.execute(statement) {
+                Ok(()) => {}
+                Err(Unwind::Error(RuntimeError {
+                    kind: RuntimeErrorKind::Exit(code),
+                    ..
+                })) => {
+                    self.exit_code = Some(code);
+                    return Ok(());
+                }
+                Err(Unwind::Error(err)) => return Err(err),
+                // The resolver rejects top-level returns, so this only ends
+                // the script early if one slips through.
+                Err(Unwind::Return(_)) => return Ok(()),
+            }
         }
         Ok(())
     }
 
-    fn execute(&mut self, statement: &Stmt) -> Result<(), RuntimeError> {
+    /// Calls a function value after checking the number of arguments. Native
+    /// functions use this to call back into Lox; errors raised inside the
+    /// callee keep their own location, and the rest have none until the
+    /// caller attaches one.
+    pub fn call_value(
+        &mut self,
+        callee: &Value,
+        arguments: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        let Value::Callable(callable) = callee else {
+            return Err(RuntimeErrorKind::TypeMismatch(
+                "Can only call functions and classes.".into(),
+            )
+            .into());
+        };
+        let got = arguments.len();
+        let (min, max) = (callable.arity(), callable.max_arity());
+        if got < min || max.is_some_and(|max| got > max) {
+            let expected = match max {
+                Some(max) if max == min => min.to_string(),
+                Some(max) => format!("{} to {}", min, max),
+                None => format!("at least {}", min),
+            };
+            return Err(RuntimeErrorKind::TypeMismatch(format!(
+                "Expected {} arguments but got {}.",
+                expected, got
+            ))
+            .into());
+        }
+        callable.call(self, arguments)
+    }
+
+    /// Runs a function body in `environment` and returns the value of its
+    /// `return`, or nil if it finishes without one. Fails with a stack
+    /// overflow once calls nest deeper than [`MAX_CALL_DEPTH`].
+    pub(crate) fn execute_function_body(
+        &mut self,
+        body: &[Stmt],
+        environment: Rc<RefCell<Environment>>,
+    ) -> Result<Value, RuntimeError> {
+        if self.call_depth >= MAX_CALL_DEPTH {
+            return Err(RuntimeErrorKind::StackOverflow.into());
+        }
+        self.call_depth += 1;
+        let result = self.execute_block(body, environment);
+        self.call_depth -= 1;
+        match result {
+            Ok(()) => Ok(Value::Nil),
+            Err(Unwind::Return(value)) => Ok(value),
+            Err(Unwind::Error(err)) => Err(err),
+        }
+    }
+
+    fn execute(&mut self, statement: &Stmt) -> Result<(), Unwind> {
         match statement {
             Stmt::Expression { expression } => {
                 self.evaluate(expression)?;
@@ -43,7 +408,8 @@ impl Interpreter {
             }
             Stmt::Print { expression } => {
                 let value = self.evaluate(expression)?;
-                println!("{}", value);
+                writeln!(self.output, "{}", value)
+                    .map_err(|err| RuntimeError::from(RuntimeErrorKind::Io(err.to_string())))?;
                 Ok(())
             }
             Stmt::Block { statements } => {
@@ -82,6 +448,21 @@ impl Interpreter {
                 }
                 Ok(())
             }
+            Stmt::Function { name, params, body } => {
+                let function =
+                    LoxFunction::new(params.clone(), body.clone(), self.environment.clone());
+                self.environment
+                    .borrow_mut()
+                    .define(name.lexeme.clone(), Value::Callable(Rc::new(function)));
+                Ok(())
+            }
+            Stmt::Return { value, .. } => {
+                let value = match value {
+                    Some(expr) => self.evaluate(expr)?,
+                    None => Value::Nil,
+                };
+                Err(Unwind::Return(value))
+            }
         }
     }
 
@@ -89,7 +470,7 @@ impl Interpreter {
         &mut self,
         statements: &[Stmt],
         environment: Rc<RefCell<Environment>>,
-    ) -> Result<(), RuntimeError> {
+    ) -> Result<(), Unwind> {
         let previous = self.environment.clone();
         self.environment = environment;
 
@@ -101,6 +482,214 @@ impl Interpreter {
         result
     }
 
+    /// Reads an assignable `target`, stores `compute` applied to it and
+    /// returns the previous and updated values. Any sub-expressions of the
+    /// target are evaluated only once.
+    fn update(
+        &mut self,
+        target: &Expr,
+        compute: impl FnOnce(&mut Self, Value) -> Result<Value, RuntimeError>,
+    ) -> Result<(Value, Value), RuntimeError> {
+        match target {
+            Expr::Variable { name } => {
+                let previous = self.evaluate(target)?;
+                let updated = compute(self, previous.clone())?;
+                if !self
+                    .environment
+                    .borrow_mut()
+                    .assign(&name.lexeme, updated.clone())
+                {
+                    return Err(RuntimeError::new(
+                        RuntimeErrorKind::UndefinedVariable(name.lexeme.clone()),
+                        name,
+                    ));
+                }
+                Ok((previous, updated))
+            }
+            Expr::Index {
+                object,
+                bracket,
+                index,
+            } => {
+                let object = self.evaluate(object)?;
+                let index = self.evaluate(index)?;
+                let previous = Self::index_get(&object, &index, bracket)?;
+                let updated = compute(self, previous.clone())?;
+                Self::index_set(&object, &index, updated.clone(), bracket)?;
+                Ok((previous, updated))
+            }
+            Expr::Get { object, name } => {
+                let object = self.evaluate(object)?;
+                let previous = Self::property_get(&object, name)?;
+                let updated = compute(self, previous.clone())?;
+                Self::property_set(&object, name, updated.clone())?;
+                Ok((previous, updated))
+            }
+            // The parser only builds updates of assignable expressions.
+            _ => unreachable!(),
+        }
+    }
+
+    fn index_get(object: &Value, index: &Value, bracket: &Token) -> Result<Value, RuntimeError> {
+        match object {
+            Value::List(list) => {
+                let list = list.borrow();
+                let position = checked_index(index, list.len())
+                    .map_err(|kind| RuntimeError::new(kind, bracket))?;
+                Ok(list[position].clone())
+            }
+            Value::Str(text) => {
+                let position = checked_index(index, text.chars().count())
+                    .map_err(|kind| RuntimeError::new(kind, bracket))?;
+                Ok(Value::Str(text.chars().nth(position).unwrap().to_string()))
+            }
+            Value::Map(map) => Ok(map
+                .borrow()
+                .get(map_key(index, bracket)?)
+                .cloned()
+                .unwrap_or(Value::Nil)),
+            _ => Err(RuntimeError::new(
+                RuntimeErrorKind::TypeMismatch(
+                    "Only lists, strings and maps can be indexed.".into(),
+                ),
+                bracket,
+            )),
+        }
+    }
+
+    fn index_set(
+        object: &Value,
+        index: &Value,
+        value: Value,
+        bracket: &Token,
+    ) -> Result<(), RuntimeError> {
+        match object {
+            Value::List(list) => {
+                let mut list = list.borrow_mut();
+                let position = checked_index(index, list.len())
+                    .map_err(|kind| RuntimeError::new(kind, bracket))?;
+                list[position] = value;
+                Ok(())
+            }
+            Value::Map(map) => {
+                let key = map_key(index, bracket)?.to_string();
+                map.borrow_mut().insert(key, value);
+                Ok(())
+            }
+            _ => Err(RuntimeError::new(
+                RuntimeErrorKind::TypeMismatch(
+                    "Only lists and maps support index assignment.".into(),
+                ),
+                bracket,
+            )),
+        }
+    }
+
+    /// Reads `object.name`. Unlike `object["name"]`, which gives nil for a
+    /// missing key, a missing property is an error, since it is usually a
+    /// typo.
+    fn property_get(object: &Value, name: &Token) -> Result<Value, RuntimeError> {
+        let Value::Map(map) = object else {
+            return Err(RuntimeError::new(
+                RuntimeErrorKind::TypeMismatch("Only maps have properties.".into()),
+                name,
+            ));
+        };
+        map.borrow().get(&name.lexeme).cloned().ok_or_else(|| {
+            RuntimeError::new(
+                RuntimeErrorKind::UndefinedProperty(name.lexeme.clone()),
+                name,
+            )
+        })
+    }
+
+    fn property_set(object: &Value, name: &Token, value: Value) -> Result<(), RuntimeError> {
+        let Value::Map(map) = object else {
+            return Err(RuntimeError::new(
+                RuntimeErrorKind::TypeMismatch("Only maps have properties.".into()),
+                name,
+            ));
+        };
+        map.borrow_mut().insert(name.lexeme.clone(), value);
+        Ok(())
+    }
+
+    /// Applies a binary operator to two evaluated operands. Compound
+    /// assignments share this with `Expr::Binary`, so `operator` may be the
+    /// `+=` token standing in for `+`.
+    fn binary(
+        left: Value,
+        operator_type: TokenType,
+        right: Value,
+        operator: &Token,
+    ) -> Result<Value, RuntimeError> {
+        let type_error = |message: &str| {
+            Err(RuntimeError::new(
+                RuntimeErrorKind::TypeMismatch(message.into()),
+                operator,
+            ))
+        };
+        match (left, operator_type, right) {
+            (Value::Str(s1), TokenType::Plus, Value::Str(s2)) => {
+                Ok(Value::Str(format!("{}{}", s1, s2)))
+            }
+            (v1, TokenType::EqualEqual, v2) => Ok(Value::Boolean(v1 == v2)),
+            (v1, TokenType::BangEqual, v2) => Ok(Value::Boolean(v1 != v2)),
+
+            (
+                left,
+                TokenType::Greater
+                | TokenType::GreaterEqual
+                | TokenType::Less
+                | TokenType::LessEqual,
+                right,
+            ) => {
+                if !is_number(&left) || !is_number(&right) {
+                    return type_error("Operands must be numbers.");
+                }
+                // NaN compares false with everything.
+                let ordering = left.compare_numbers(&right);
+                Ok(Value::Boolean(match operator_type {
+                    TokenType::Greater => ordering == Some(Ordering::Greater),
+                    TokenType::GreaterEqual => {
+                        matches!(ordering, Some(Ordering::Greater | Ordering::Equal))
+                    }
+                    TokenType::Less => ordering == Some(Ordering::Less),
+                    _ => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+                }))
+            }
+
+            (
+                left,
+                TokenType::Ampersand
+                | TokenType::Pipe
+                | TokenType::Caret
+                | TokenType::LessLess
+                | TokenType::GreaterGreater,
+                right,
+            ) => bitwise(&left, operator_type, &right)
+                .map_err(|kind| RuntimeError::new(kind, operator)),
+
+            (
+                left @ (Value::Int(_) | Value::BigInt(_)),
+                _,
+                right @ (Value::Int(_) | Value::BigInt(_)),
+            ) => integer_arithmetic(&left, operator_type, &right)
+                .map_err(|kind| RuntimeError::new(kind, operator)),
+            (left, _, right) => {
+                let (Some(a), Some(b)) = (as_float(&left), as_float(&right)) else {
+                    return if operator_type == TokenType::Plus {
+                        type_error("Operands must be two numbers or two strings.")
+                    } else {
+                        type_error("Operands must be numbers.")
+                    };
+                };
+                float_arithmetic(a, operator_type, b)
+                    .map_err(|kind| RuntimeError::new(kind, operator))
+            }
+        }
+    }
+
     pub fn evaluate(&mut self, expr: &Expr) -> Result<Value, RuntimeError> {
         match expr {
             Expr::Binary {
@@ -110,52 +699,13 @@ impl Interpreter {
             } => {
                 let left_value = self.evaluate(left)?;
                 let right_value = self.evaluate(right)?;
-                match (left_value, operator.token_type.clone(), right_value) {
-                    (Value::Number(n1), TokenType::Minus, Value::Number(n2)) => {
-                        Ok(Value::Number(n1 - n2))
-                    }
-                    (Value::Number(n1), TokenType::Slash, Value::Number(n2)) => {
-                        if n2 == 0.0 {
-                            return Err(RuntimeError::ZeroDivision);
-                        }
-                        Ok(Value::Number(n1 / n2))
-                    }
-                    (Value::Number(n1), TokenType::Star, Value::Number(n2)) => {
-                        Ok(Value::Number(n1 * n2))
-                    }
-
-                    (Value::Number(n1), TokenType::Plus, Value::Number(n2)) => {
-                        Ok(Value::Number(n1 + n2))
-                    }
-                    (Value::Str(s1), TokenType::Plus, Value::Str(s2)) => {
-                        Ok(Value::Str(format!("{}{}", s1, s2)))
-                    }
-
-                    (Value::Number(n1), TokenType::Greater, Value::Number(n2)) => {
-                        Ok(Value::Boolean(n1 > n2))
-                    }
-                    (Value::Number(n1), TokenType::Less, Value::Number(n2)) => {
-                        Ok(Value::Boolean(n1 < n2))
-                    }
-
-                    (v1, TokenType::EqualEqual, v2) => Ok(Value::Boolean(v1 == v2)),
-                    (v1, TokenType::BangEqual, v2) => Ok(Value::Boolean(v1 != v2)),
-
-                    (_, TokenType::Plus, _) => Err(RuntimeError::TypeMismatch(
-                        "Operands must be two numbers or two strings.".into(),
-                    )),
-                    (_, TokenType::Minus, _)
-                    | (_, TokenType::Star, _)
-                    | (_, TokenType::Slash, _) => Err(RuntimeError::TypeMismatch(
-                        "Operands must be numbers.".into(),
-                    )),
-
-                    _ => unreachable!(),
-                }
+                Self::binary(left_value, operator.token_type, right_value, operator)
             }
             Expr::Grouping { expression } => self.evaluate(expression),
             Expr::Literal { value } => match &value.literal {
                 Some(Literal::Number(number)) => Ok(Value::Number(*number)),
+                Some(Literal::Int(number)) => Ok(Value::Int(*number)),
+                Some(Literal::BigInt(number)) => Ok(Value::BigInt(number.clone())),
                 Some(Literal::Str(text)) => Ok(Value::Str(text.clone())),
                 Some(Literal::Bool(value)) => Ok(Value::Boolean(*value)),
                 Some(Literal::Nil) => Ok(Value::Nil),
@@ -164,7 +714,10 @@ impl Interpreter {
                     TokenType::True => Ok(Value::Boolean(true)),
                     TokenType::False => Ok(Value::Boolean(false)),
                     TokenType::Nil => Ok(Value::Nil),
-                    _ => Err(RuntimeError::TypeMismatch("Expected literal value.".into())),
+                    _ => Err(RuntimeError::new(
+                        RuntimeErrorKind::TypeMismatch("Expected literal value.".into()),
+                        value,
+                    )),
                 },
             },
             Expr::Logical {
@@ -192,9 +745,28 @@ impl Interpreter {
                     _ => unreachable!(),
                 }
             }
+            Expr::Conditional {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                if self.evaluate(condition)?.is_truthy() {
+                    self.evaluate(then_branch)
+                } else {
+                    self.evaluate(else_branch)
+                }
+            }
+            Expr::Coalesce { left, right, .. } => match self.evaluate(left)? {
+                Value::Nil => self.evaluate(right),
+                value => Ok(value),
+            },
             Expr::Variable { name } => match self.environment.borrow().get(&name.lexeme) {
                 Some(value) => Ok(value),
-                None => Err(RuntimeError::UndefinedVariable(name.lexeme.clone())),
+                None => Err(RuntimeError::new(
+                    RuntimeErrorKind::UndefinedVariable(name.lexeme.clone()),
+                    name,
+                )),
             },
             Expr::Assign { name, value } => {
                 let evaluated = self.evaluate(value)?;
@@ -205,19 +777,83 @@ impl Interpreter {
                 {
                     Ok(evaluated)
                 } else {
-                    Err(RuntimeError::UndefinedVariable(name.lexeme.clone()))
+                    Err(RuntimeError::new(
+                        RuntimeErrorKind::UndefinedVariable(name.lexeme.clone()),
+                        name,
+                    ))
                 }
             }
+            Expr::CompoundAssign {
+                target,
+                operator,
+                value,
+            } => {
+                let operator_type = match operator.token_type {
+                    TokenType::PlusEqual => TokenType::Plus,
+                    TokenType::MinusEqual => TokenType::Minus,
+                    TokenType::StarEqual => TokenType::Star,
+                    TokenType::SlashEqual => TokenType::Slash,
+                    TokenType::PercentEqual => TokenType::Percent,
+                    _ => unreachable!(),
+                };
+                let (_, updated) = self.update(target, |interpreter, current| {
+                    let right = interpreter.evaluate(value)?;
+                    Self::binary(current, operator_type, right, operator)
+                })?;
+                Ok(updated)
+            }
+            Expr::Increment {
+                target,
+                operator,
+                prefix,
+            } => {
+                let delta = match operator.token_type {
+                    TokenType::PlusPlus => 1,
+                    _ => -1,
+                };
+                let (previous, updated) = self.update(target, |_, current| match current {
+                    Value::Number(number) => Ok(Value::Number(number + delta as f64)),
+                    Value::Int(_) | Value::BigInt(_) => {
+                        integer_arithmetic(&current, TokenType::Plus, &Value::Int(delta))
+                            .map_err(|kind| RuntimeError::new(kind, operator))
+                    }
+                    _ => Err(RuntimeError::new(
+                        RuntimeErrorKind::TypeMismatch("Operand must be a number.".into()),
+                        operator,
+                    )),
+                })?;
+                Ok(if *prefix { updated } else { previous })
+            }
             Expr::Unary { operator, right } => {
                 let right_value = self.evaluate(right)?;
                 match operator.token_type {
                     TokenType::Minus => match right_value {
                         Value::Number(number) => Ok(Value::Number(-number)),
-                        _ => Err(RuntimeError::TypeMismatch(
-                            "Operand must be a number.".into(),
+                        Value::Int(number) => Ok(number
+                            .checked_neg()
+                            .map_or_else(|| normalize(-&BigInt::from(number)), Value::Int)),
+                        Value::BigInt(number) => Ok(normalize(-&number)),
+                        _ => Err(RuntimeError::new(
+                            RuntimeErrorKind::TypeMismatch("Operand must be a number.".into()),
+                            operator,
                         )),
                     },
                     TokenType::Bang => Ok(Value::Boolean(!right_value.is_truthy())),
+                    TokenType::Tilde => {
+                        if let Some(number) = integral(&right_value) {
+                            Ok(Value::Int(!number))
+                        } else if let Some(number) = big_integral(&right_value) {
+                            // In two's complement, `~x` is `-x - 1`.
+                            Ok(normalize(&-&number - &BigInt::from(1)))
+                        } else {
+                            Err(RuntimeError::new(
+                                RuntimeErrorKind::TypeMismatch(
+                                    "Operand must be an integer.".into(),
+                                ),
+                                operator,
+                            ))
+                        }
+                    }
                     _ => unreachable!(),
                 }
             }
@@ -233,23 +869,293 @@ impl Interpreter {
                     evaluated_args.push(self.evaluate(argument)?);
                 }
 
-                match callee_value {
-                    Value::Callable(callable) => {
-                        let expected = callable.arity();
-                        let got = evaluated_args.len();
-                        if expected != got {
-                            return Err(RuntimeError::TypeMismatch(format!(
-                                "Expected {} arguments but got {}.",
-                                expected, got
-                            )));
-                        }
-                        callable.call(self, evaluated_args)
-                    }
-                    _ => Err(RuntimeError::TypeMismatch(
-                        "Can only call functions and classes.".into(),
-                    )),
+                self.call_value(&callee_value, evaluated_args)
+                    .map_err(|err| err.or_at(paren))
+            }
+            Expr::List { elements, .. } => {
+                let mut values = Vec::with_capacity(elements.len());
+                for element in elements {
+                    values.push(self.evaluate(element)?);
                 }
+                Ok(Value::list(values))
+            }
+            Expr::Index {
+                object,
+                bracket,
+                index,
+            } => {
+                let object = self.evaluate(object)?;
+                let index = self.evaluate(index)?;
+                Self::index_get(&object, &index, bracket)
+            }
+            Expr::IndexAssign {
+                object,
+                bracket,
+                index,
+                value,
+            } => {
+                let object = self.evaluate(object)?;
+                let index = self.evaluate(index)?;
+                let value = self.evaluate(value)?;
+                Self::index_set(&object, &index, value.clone(), bracket)?;
+                Ok(value)
+            }
+            Expr::Map { brace, entries } => {
+                let mut map = LoxMap::new();
+                for (key, value) in entries {
+                    let key = self.evaluate(key)?;
+                    let key = map_key(&key, brace)?.to_string();
+                    map.insert(key, self.evaluate(value)?);
+                }
+                Ok(Value::map(map))
+            }
+            Expr::Get { object, name } => {
+                let object = self.evaluate(object)?;
+                Self::property_get(&object, name)
+            }
+            Expr::Set {
+                object,
+                name,
+                value,
+            } => {
+                let object = self.evaluate(object)?;
+                let value = self.evaluate(value)?;
+                Self::property_set(&object, name, value.clone())?;
+                Ok(value)
+            }
+            Expr::Interpolation { parts } => {
+                let mut text = String::new();
+                for part in parts {
+                    text.push_str(&self.evaluate(part)?.to_string());
+                }
+                Ok(Value::Str(text))
+            }
+            Expr::Function { params, body, .. } => Ok(Value::Callable(Rc::new(LoxFunction::new(
+                params.clone(),
+                body.clone(),
+                self.environment.clone(),
+            )))),
+        }
+    }
+}
+
+/// Checks that `index` is a whole number in `0..length`.
+pub(crate) fn checked_index(index: &Value, length: usize) -> Result<usize, RuntimeErrorKind> {
+    let Some(index) = integral(index) else {
+        return Err(RuntimeErrorKind::TypeMismatch(
+            "Index must be an integer.".into(),
+        ));
+    };
+    usize::try_from(index)
+        .ok()
+        .filter(|position| *position < length)
+        .ok_or(RuntimeErrorKind::IndexOutOfRange { index, length })
+}
+
+fn map_key<'a>(key: &'a Value, token: &Token) -> Result<&'a str, RuntimeError> {
+    match key {
+        Value::Str(key) => Ok(key),
+        _ => Err(RuntimeError::new(
+            RuntimeErrorKind::TypeMismatch("Map keys must be strings.".into()),
+            token,
+        )),
+    }
+}
+
+fn is_number(value: &Value) -> bool {
+    matches!(value, Value::Number(_) | Value::Int(_) | Value::BigInt(_))
+}
+
+/// Any number as the nearest float, or `None` for other values.
+pub(crate) fn as_float(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(number) => Some(*number),
+        Value::Int(number) => Some(*number as f64),
+        Value::BigInt(number) => Some(number.to_f64()),
+        _ => None,
+    }
+}
+
+/// The value as an `i64` if it is a whole number in that range.
+fn integral(value: &Value) -> Option<i64> {
+    match value {
+        Value::Int(number) => Some(*number),
+        Value::Number(number)
+            if number.fract() == 0.0 && (i64::MIN as f64..i64::MAX as f64).contains(number) =>
+        {
+            Some(*number as i64)
+        }
+        _ => None,
+    }
+}
+
+/// The value as a `BigInt` if it is a whole number of any size.
+fn big_integral(value: &Value) -> Option<BigInt> {
+    match value {
+        Value::Int(number) => Some(BigInt::from(*number)),
+        Value::BigInt(number) => Some(number.clone()),
+        Value::Number(number) => BigInt::from_f64(*number),
+        _ => None,
+    }
+}
+
+/// The bitwise operators treat integers as two's complement of unlimited
+/// width, so they accept big integers and `<<` grows into one rather than
+/// overflowing. Whole floats count as integers.
+fn bitwise(
+    left: &Value,
+    operator_type: TokenType,
+    right: &Value,
+) -> Result<Value, RuntimeErrorKind> {
+    let shift = matches!(
+        operator_type,
+        TokenType::LessLess | TokenType::GreaterGreater
+    );
+    if let (Some(a), Some(b), false) = (integral(left), integral(right), shift) {
+        return Ok(Value::Int(match operator_type {
+            TokenType::Ampersand => a & b,
+            TokenType::Pipe => a | b,
+            _ => a ^ b,
+        }));
+    }
+    let (Some(a), Some(b)) = (big_integral(left), big_integral(right)) else {
+        return Err(RuntimeErrorKind::TypeMismatch(
+            "Operands must be integers.".into(),
+        ));
+    };
+    let result = match operator_type {
+        TokenType::Ampersand => &a & &b,
+        TokenType::Pipe => &a | &b,
+        TokenType::Caret => &a ^ &b,
+        _ => {
+            let Some(amount @ 0..64) = b.to_i64() else {
+                return Err(RuntimeErrorKind::TypeMismatch(
+                    "Shift amount must be between 0 and 63.".into(),
+                ));
+            };
+            if operator_type == TokenType::LessLess {
+                a.shl(amount as u32)
+            } else {
+                a.shr(amount as u32)
             }
         }
+    };
+    Ok(normalize(result))
+}
+
+/// The largest result `**` will build, about 315,000 decimal digits.
+const MAX_POWER_BITS: u64 = 1 << 20;
+
+/// Arithmetic on integers stays exact, moving to `BigInt` when a result
+/// overflows `i64`. The exceptions are `/`, which always produces a float,
+/// and `**` with a negative exponent.
+pub(crate) fn integer_arithmetic(
+    left: &Value,
+    operator_type: TokenType,
+    right: &Value,
+) -> Result<Value, RuntimeErrorKind> {
+    if let (Value::Int(a), Value::Int(b)) = (left, right)
+        && let Some(result) = int_arithmetic(*a, operator_type, *b)?
+    {
+        return Ok(result);
     }
+    big_arithmetic(&to_big(left), operator_type, &to_big(right))
+}
+
+/// The fast path for two ints; `None` means the result overflowed.
+fn int_arithmetic(
+    a: i64,
+    operator_type: TokenType,
+    b: i64,
+) -> Result<Option<Value>, RuntimeErrorKind> {
+    let result = match operator_type {
+        TokenType::Plus => a.checked_add(b),
+        TokenType::Minus => a.checked_sub(b),
+        TokenType::Star => a.checked_mul(b),
+        TokenType::StarStar => match u32::try_from(b) {
+            Ok(exponent) => a.checked_pow(exponent),
+            Err(_) => return big_arithmetic(&a.into(), operator_type, &b.into()).map(Some),
+        },
+        _ if b == 0 => return Err(RuntimeErrorKind::ZeroDivision),
+        TokenType::Slash => return Ok(Some(Value::Number(a as f64 / b as f64))),
+        TokenType::TildeSlash => a.checked_div(b).map(|quotient| {
+            let rounds_down = a % b != 0 && (a < 0) != (b < 0);
+            if rounds_down { quotient - 1 } else { quotient }
+        }),
+        TokenType::Percent => {
+            let remainder = a.wrapping_rem(b);
+            let adjust = remainder != 0 && (remainder < 0) != (b < 0);
+            Some(if adjust { remainder + b } else { remainder })
+        }
+        _ => unreachable!(),
+    };
+    Ok(result.map(Value::Int))
+}
+
+fn big_arithmetic(
+    a: &BigInt,
+    operator_type: TokenType,
+    b: &BigInt,
+) -> Result<Value, RuntimeErrorKind> {
+    match operator_type {
+        TokenType::Plus => Ok(normalize(a + b)),
+        TokenType::Minus => Ok(normalize(a - b)),
+        TokenType::Star => Ok(normalize(a * b)),
+        TokenType::StarStar if b.is_negative() => {
+            float_arithmetic(a.to_f64(), operator_type, b.to_f64())
+        }
+        TokenType::StarStar => match b.to_i64().and_then(|b| u32::try_from(b).ok()) {
+            // Refuse results that would take an unreasonable amount of memory.
+            Some(exponent) if a.bits() > 1 && a.bits() * exponent as u64 > MAX_POWER_BITS => {
+                Err(RuntimeErrorKind::Overflow)
+            }
+            Some(exponent) => Ok(normalize(a.pow(exponent))),
+            None => Err(RuntimeErrorKind::Overflow),
+        },
+        _ if b.is_zero() => Err(RuntimeErrorKind::ZeroDivision),
+        TokenType::Slash => Ok(Value::Number(a.to_f64() / b.to_f64())),
+        TokenType::TildeSlash | TokenType::Percent => {
+            let (quotient, remainder) = a.div_rem_floor(b).ok_or(RuntimeErrorKind::ZeroDivision)?;
+            Ok(normalize(if operator_type == TokenType::TildeSlash {
+                quotient
+            } else {
+                remainder
+            }))
+        }
+        _ => unreachable!(),
+    }
+}
+
+fn to_big(value: &Value) -> BigInt {
+    match value {
+        Value::Int(value) => BigInt::from(*value),
+        Value::BigInt(value) => value.clone(),
+        _ => unreachable!(),
+    }
+}
+
+/// Keeps integers that fit in `i64` on the fast path, so a value has only
+/// one integer representation.
+pub(crate) fn normalize(value: BigInt) -> Value {
+    match value.to_i64() {
+        Some(value) => Value::Int(value),
+        None => Value::BigInt(value),
+    }
+}
+
+fn float_arithmetic(a: f64, operator_type: TokenType, b: f64) -> Result<Value, RuntimeErrorKind> {
+    let value = match operator_type {
+        TokenType::Plus => a + b,
+        TokenType::Minus => a - b,
+        TokenType::Star => a * b,
+        TokenType::StarStar => a.powf(b),
+        _ if b == 0.0 => return Err(RuntimeErrorKind::ZeroDivision),
+        TokenType::Slash => a / b,
+        TokenType::TildeSlash => (a / b).floor(),
+        // Floored like `~/`, so the result takes the sign of the divisor and
+        // `a == (a ~/ b) * b + a % b`.
+        TokenType::Percent => a - b * (a / b).floor(),
+        _ => unreachable!(),
+    };
+    Ok(Value::Number(value))
 }
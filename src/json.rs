@@ -0,0 +1,459 @@
+//! JSON: quoting strings for diagnostics and printed lists, and parsing and
+//! serializing whole values for the `json` native module.
+
+use crate::bigint::BigInt;
+use crate::cursor::Cursor;
+use crate::lox_map::LoxMap;
+use crate::value::Value;
+use std::fmt::{self, Write};
+use std::rc::Rc;
+
+/// Quotes `text` as a JSON string literal.
+pub fn quote(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\u{08}' => out.push_str("\\b"),
+            '\u{0c}' => out.push_str("\\f"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// A syntax error in JSON text, with the 1-based line and column of the
+/// character where it was found.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonError {
+    pub message: String,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} at line {}, column {}",
+            self.message, self.line, self.column
+        )
+    }
+}
+
+/// Objects nested deeper than this are rejected, when parsing or writing,
+/// rather than risking the stack.
+pub(crate) const MAX_DEPTH: usize = 512;
+
+/// Parses JSON text into Lox values: objects become maps, arrays lists, and
+/// numbers integers when they have no fraction or exponent.
+pub fn parse(text: &str) -> Result<Value, JsonError> {
+    let mut parser = JsonParser {
+        source: text.chars().collect(),
+        current: 0,
+    };
+    let value = parser.value(0)?;
+    parser.skip_whitespace();
+    if !parser.is_at_end() {
+        return Err(parser.error("Unexpected character after JSON value"));
+    }
+    Ok(value)
+}
+
+struct JsonParser {
+    source: Vec<char>,
+    current: usize,
+}
+
+impl Cursor<char> for JsonParser {
+    fn source(&self) -> &[char] {
+        &self.source
+    }
+
+    fn current(&self) -> usize {
+        self.current
+    }
+
+    fn current_mut(&mut self) -> &mut usize {
+        &mut self.current
+    }
+
+    fn is_at_end(&self) -> bool {
+        self.current >= self.source.len()
+    }
+}
+
+impl JsonParser {
+    /// An error at the current character.
+    fn error(&self, message: &str) -> JsonError {
+        self.error_at(self.current, message)
+    }
+
+    fn error_at(&self, position: usize, message: &str) -> JsonError {
+        let before = &self.source[..position.min(self.source.len())];
+        let line = before.iter().filter(|c| **c == '\n').count() + 1;
+        let column = before.iter().rev().take_while(|c| **c != '\n').count() + 1;
+        JsonError {
+            message: message.to_string(),
+            line,
+            column,
+        }
+    }
+
+    fn peek_char(&self) -> Option<char> {
+        self.source.get(self.current).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek_char(), Some(' ' | '\t' | '\n' | '\r')) {
+            self.advance();
+        }
+    }
+
+    fn expect(&mut self, expected: char, message: &str) -> Result<(), JsonError> {
+        self.skip_whitespace();
+        if self.peek_char() == Some(expected) {
+            self.advance();
+            Ok(())
+        } else {
+            Err(self.error(message))
+        }
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Value, JsonError> {
+        self.skip_whitespace();
+        if depth > MAX_DEPTH {
+            return Err(self.error("Nesting is too deep"));
+        }
+        match self.peek_char() {
+            Some('{') => self.object(depth),
+            Some('[') => self.array(depth),
+            Some('"') => self.string().map(Value::Str),
+            Some('-' | '0'..='9') => self.number(),
+            Some('t') => self.keyword("true", Value::Boolean(true)),
+            Some('f') => self.keyword("false", Value::Boolean(false)),
+            Some('n') => self.keyword("null", Value::Nil),
+            Some(_) => Err(self.error("Expect a JSON value")),
+            None => Err(self.error("Unexpected end of input")),
+        }
+    }
+
+    fn keyword(&mut self, word: &str, value: Value) -> Result<Value, JsonError> {
+        for expected in word.chars() {
+            if self.peek_char() != Some(expected) {
+                return Err(self.error("Expect a JSON value"));
+            }
+            self.advance();
+        }
+        Ok(value)
+    }
+
+    fn object(&mut self, depth: usize) -> Result<Value, JsonError> {
+        self.advance();
+        let mut map = LoxMap::new();
+        self.skip_whitespace();
+        if self.peek_char() == Some('}') {
+            self.advance();
+            return Ok(Value::map(map));
+        }
+        loop {
+            self.skip_whitespace();
+            if self.peek_char() != Some('"') {
+                return Err(self.error("Expect a string key"));
+            }
+            let key = self.string()?;
+            self.expect(':', "Expect ':' after object key")?;
+            let value = self.value(depth + 1)?;
+            map.insert(key, value);
+            self.skip_whitespace();
+            match self.peek_char() {
+                Some(',') => {
+                    self.advance();
+                }
+                Some('}') => {
+                    self.advance();
+                    return Ok(Value::map(map));
+                }
+                _ => return Err(self.error("Expect ',' or '}' after object entry")),
+            }
+        }
+    }
+
+    fn array(&mut self, depth: usize) -> Result<Value, JsonError> {
+        self.advance();
+        let mut elements = Vec::new();
+        self.skip_whitespace();
+        if self.peek_char() == Some(']') {
+            self.advance();
+            return Ok(Value::list(elements));
+        }
+        loop {
+            elements.push(self.value(depth + 1)?);
+            self.skip_whitespace();
+            match self.peek_char() {
+                Some(',') => {
+                    self.advance();
+                }
+                Some(']') => {
+                    self.advance();
+                    return Ok(Value::list(elements));
+                }
+                _ => return Err(self.error("Expect ',' or ']' after array element")),
+            }
+        }
+    }
+
+    /// Parses a string starting at its opening quote.
+    fn string(&mut self) -> Result<String, JsonError> {
+        self.advance();
+        let mut text = String::new();
+        loop {
+            let Some(c) = self.peek_char() else {
+                return Err(self.error("Unterminated string"));
+            };
+            self.advance();
+            match c {
+                '"' => return Ok(text),
+                '\\' => text.push(self.escape()?),
+                c if (c as u32) < 0x20 => {
+                    return Err(
+                        self.error_at(self.current - 1, "Unescaped control character in string")
+                    );
+                }
+                c => text.push(c),
+            }
+        }
+    }
+
+    /// Parses the escape after a backslash.
+    fn escape(&mut self) -> Result<char, JsonError> {
+        let start = self.current - 1;
+        let Some(c) = self.peek_char() else {
+            return Err(self.error("Unterminated string"));
+        };
+        self.advance();
+        let escaped = match c {
+            '"' => '"',
+            '\\' => '\\',
+            '/' => '/',
+            'b' => '\u{08}',
+            'f' => '\u{0c}',
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            'u' => {
+                let high = self.hex_digits(start)?;
+                let code = if (0xd800..0xdc00).contains(&high) {
+                    // A high surrogate must be followed by an escaped low one.
+                    if self.peek_char() != Some('\\')
+                        || self.source.get(self.current + 1) != Some(&'u')
+                    {
+                        return Err(self.error_at(start, "Unpaired surrogate in Unicode escape"));
+                    }
+                    self.current += 2;
+                    let low = self.hex_digits(start)?;
+                    if !(0xdc00..0xe000).contains(&low) {
+                        return Err(self.error_at(start, "Unpaired surrogate in Unicode escape"));
+                    }
+                    0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00)
+                } else {
+                    high
+                };
+                return char::from_u32(code)
+                    .ok_or_else(|| self.error_at(start, "Unpaired surrogate in Unicode escape"));
+            }
+            _ => return Err(self.error_at(start, "Invalid escape sequence")),
+        };
+        Ok(escaped)
+    }
+
+    fn hex_digits(&mut self, start: usize) -> Result<u32, JsonError> {
+        let mut code = 0;
+        for _ in 0..4 {
+            let digit = self
+                .peek_char()
+                .and_then(|c| c.to_digit(16))
+                .ok_or_else(|| self.error_at(start, "Expect four hex digits in Unicode escape"))?;
+            code = code * 16 + digit;
+            self.advance();
+        }
+        Ok(code)
+    }
+
+    fn number(&mut self) -> Result<Value, JsonError> {
+        let start = self.current;
+        if self.peek_char() == Some('-') {
+            self.advance();
+        }
+        match self.peek_char() {
+            Some('0') => {
+                self.advance();
+            }
+            Some('1'..='9') => self.digits(),
+            _ => return Err(self.error("Expect a digit")),
+        }
+        let mut is_integer = true;
+        if self.peek_char() == Some('.') {
+            is_integer = false;
+            self.advance();
+            if !matches!(self.peek_char(), Some('0'..='9')) {
+                return Err(self.error("Expect a digit after '.'"));
+            }
+            self.digits();
+        }
+        if matches!(self.peek_char(), Some('e' | 'E')) {
+            is_integer = false;
+            self.advance();
+            if matches!(self.peek_char(), Some('+' | '-')) {
+                self.advance();
+            }
+            if !matches!(self.peek_char(), Some('0'..='9')) {
+                return Err(self.error("Expect a digit in the exponent"));
+            }
+            self.digits();
+        }
+
+        let text: String = self.source[start..self.current].iter().collect();
+        if is_integer {
+            let digits = text.trim_start_matches('-');
+            let magnitude = BigInt::parse(digits, 10).unwrap_or_else(BigInt::zero);
+            let value = if text.starts_with('-') {
+                -&magnitude
+            } else {
+                magnitude
+            };
+            return Ok(match value.to_i64() {
+                Some(value) => Value::Int(value),
+                None => Value::BigInt(value),
+            });
+        }
+        match text.parse::<f64>() {
+            Ok(value) if value.is_finite() => Ok(Value::Number(value)),
+            _ => Err(self.error_at(start, "Number is out of range")),
+        }
+    }
+
+    fn digits(&mut self) {
+        while matches!(self.peek_char(), Some('0'..='9')) {
+            self.advance();
+        }
+    }
+}
+
+/// Serializes a value as JSON. With an empty `indent` the output is compact;
+/// otherwise every element goes on its own line, indented by `indent` per
+/// level. Functions, NaN, infinities and structures that contain themselves
+/// have no JSON form and are reported as errors, as is nesting deeper than
+/// parsing allows.
+pub fn stringify(value: &Value, indent: &str) -> Result<String, String> {
+    let mut writer = JsonWriter {
+        out: String::new(),
+        indent,
+        open: Vec::new(),
+    };
+    writer.value(value, 0)?;
+    Ok(writer.out)
+}
+
+struct JsonWriter<'a> {
+    out: String,
+    indent: &'a str,
+    /// The lists and maps being written, to detect cycles. A structure that
+    /// merely appears twice is fine.
+    open: Vec<*const ()>,
+}
+
+impl JsonWriter<'_> {
+    fn value(&mut self, value: &Value, depth: usize) -> Result<(), String> {
+        match value {
+            Value::Nil => self.out.push_str("null"),
+            Value::Boolean(value) => {
+                let _ = write!(self.out, "{}", value);
+            }
+            Value::Int(value) => {
+                let _ = write!(self.out, "{}", value);
+            }
+            Value::BigInt(value) => {
+                let _ = write!(self.out, "{}", value);
+            }
+            Value::Number(value) if value.is_finite() => {
+                let _ = write!(self.out, "{}", value);
+            }
+            Value::Number(_) => return Err("cannot encode NaN or infinity".to_string()),
+            Value::Str(text) => self.out.push_str(&quote(text)),
+            Value::Callable(_) => return Err("cannot encode a function".to_string()),
+            Value::List(list) => {
+                self.enter(Rc::as_ptr(list) as *const (), depth)?;
+                let list = list.borrow();
+                self.out.push('[');
+                for (index, element) in list.iter().enumerate() {
+                    self.separator(index, depth + 1);
+                    self.value(element, depth + 1)?;
+                }
+                self.close(!list.is_empty(), depth, ']');
+            }
+            Value::Map(map) => {
+                self.enter(Rc::as_ptr(map) as *const (), depth)?;
+                let map = map.borrow();
+                self.out.push('{');
+                for (index, (key, value)) in map.iter().enumerate() {
+                    self.separator(index, depth + 1);
+                    self.out.push_str(&quote(key));
+                    self.out.push(':');
+                    if !self.indent.is_empty() {
+                        self.out.push(' ');
+                    }
+                    self.value(value, depth + 1)?;
+                }
+                self.close(!map.is_empty(), depth, '}');
+            }
+        }
+        Ok(())
+    }
+
+    fn enter(&mut self, pointer: *const (), depth: usize) -> Result<(), String> {
+        if depth > MAX_DEPTH {
+            return Err(format!(
+                "cannot encode nesting deeper than {MAX_DEPTH} levels"
+            ));
+        }
+        if self.open.contains(&pointer) {
+            return Err("cannot encode a cyclic structure".to_string());
+        }
+        self.open.push(pointer);
+        Ok(())
+    }
+
+    /// Starts an element: a comma after the first, then a new line when
+    /// indenting.
+    fn separator(&mut self, index: usize, depth: usize) {
+        if index > 0 {
+            self.out.push(',');
+        }
+        self.newline(depth);
+    }
+
+    fn close(&mut self, has_elements: bool, depth: usize, bracket: char) {
+        self.open.pop();
+        if has_elements {
+            self.newline(depth);
+        }
+        self.out.push(bracket);
+    }
+
+    fn newline(&mut self, depth: usize) {
+        if !self.indent.is_empty() {
+            self.out.push('\n');
+            self.out.push_str(&self.indent.repeat(depth));
+        }
+    }
+}
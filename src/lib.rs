@@ -1,12 +1,24 @@
 pub mod app;
+pub mod bigint;
+pub mod cli;
 pub mod cursor;
+pub mod diagnostic;
 pub mod environment;
 pub mod expr;
+pub mod formatter;
 pub mod interpreter;
+pub mod json;
 pub mod lox_callable;
+pub mod lox_function;
+pub mod lox_map;
 pub mod parser;
+pub mod policy;
+pub mod random;
+pub mod resolver;
 pub mod scanner;
+pub mod stdlib;
 pub mod stmt;
+pub mod time_source;
 pub mod token;
 pub mod token_type;
 pub mod value;
@@ -0,0 +1,46 @@
+use crate::environment::Environment;
+use crate::interpreter::{Interpreter, RuntimeError};
+use crate::lox_callable::LoxCallable;
+use crate::stmt::Stmt;
+use crate::token::Token;
+use crate::value::Value;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// A function declared in Lox, either by a `fun` statement or as a lambda.
+///
+/// The environment it was created in is captured so that the body can keep
+/// reading and assigning the variables around it after that scope has ended.
+pub struct LoxFunction {
+    params: Vec<Token>,
+    body: Rc<Vec<Stmt>>,
+    closure: Rc<RefCell<Environment>>,
+}
+
+impl LoxFunction {
+    pub fn new(params: Vec<Token>, body: Rc<Vec<Stmt>>, closure: Rc<RefCell<Environment>>) -> Self {
+        LoxFunction {
+            params,
+            body,
+            closure,
+        }
+    }
+}
+
+impl LoxCallable for LoxFunction {
+    fn arity(&self) -> usize {
+        self.params.len()
+    }
+
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        arguments: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        let mut environment = Environment::new_enclosed(self.closure.clone());
+        for (param, argument) in self.params.iter().zip(arguments) {
+            environment.define(param.lexeme.clone(), argument);
+        }
+        interpreter.execute_function_body(&self.body, Rc::new(RefCell::new(environment)))
+    }
+}
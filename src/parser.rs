@@ -1,12 +1,44 @@
 use crate::cursor::Cursor;
+use crate::diagnostic::Diagnostic;
 use crate::expr::Expr;
 use crate::stmt::Stmt;
 use crate::token::Token;
 use crate::token_type::TokenType;
+use std::fmt;
+use std::rc::Rc;
 
+/// The token is boxed to keep the `Result`s the parser passes around small.
 #[derive(Debug)]
 pub enum ParseError {
-    Error(Token, String),
+    Error(Box<Token>, String),
+}
+
+impl From<&ParseError> for Diagnostic {
+    fn from(err: &ParseError) -> Self {
+        match err {
+            ParseError::Error(token, message) => {
+                Diagnostic::error("E0100", message.clone(), Some(token.span))
+            }
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Error(token, message) => {
+                if token.token_type == TokenType::EOF {
+                    write!(f, "[line {}] Error at end: {}", token.line, message)
+                } else {
+                    write!(
+                        f,
+                        "[line {}] Error at '{}': {}",
+                        token.line, token.lexeme, message
+                    )
+                }
+            }
+        }
+    }
 }
 
 pub struct Parser {
@@ -15,31 +47,97 @@ pub struct Parser {
 }
 
 impl Parser {
+    /// Creates a parser over `tokens`, skipping the `Error` tokens the
+    /// scanner leaves behind; those have already been reported.
     pub fn new(tokens: Vec<Token>) -> Self {
+        let tokens = tokens
+            .into_iter()
+            .filter(|token| token.token_type != TokenType::Error)
+            .collect();
         Parser { tokens, current: 0 }
     }
 
-    pub fn parse(&mut self) -> Result<Vec<Stmt>, ParseError> {
+    pub fn parse(&mut self) -> Result<Vec<Stmt>, Vec<ParseError>> {
         let mut statements = Vec::new();
+        let mut errors = Vec::new();
         while !self.is_at_end() {
             match self.declaration() {
                 Ok(stmt) => statements.push(stmt),
                 Err(err) => {
-                    return Err(err);
+                    errors.push(err);
                     self.synchronize();
                 }
             }
         }
-        Ok(statements)
+        if errors.is_empty() {
+            Ok(statements)
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Parses the whole token stream as a single expression.
+    pub fn parse_expression(&mut self) -> Result<Expr, ParseError> {
+        let expr = self.expression()?;
+        if !self.is_at_end() {
+            return Err(ParseError::Error(
+                Box::new(self.peek().clone()),
+                "Expect end of expression.".to_string(),
+            ));
+        }
+        Ok(expr)
     }
 
     fn declaration(&mut self) -> Result<Stmt, ParseError> {
         if self.match_token(&[TokenType::Var]) {
             return self.var_declaration();
         }
+        // `fun` followed by a name declares a function; otherwise it starts
+        // a function expression.
+        if self.check(&TokenType::Fun)
+            && matches!(self.peek_next(), Some(next) if next.token_type == TokenType::Identifier)
+        {
+            self.advance();
+            return self.function_declaration();
+        }
         self.statement()
     }
 
+    fn function_declaration(&mut self) -> Result<Stmt, ParseError> {
+        let name = self.consume(TokenType::Identifier, "Expect function name.")?;
+        self.consume(TokenType::LeftParen, "Expect '(' after function name.")?;
+        let params = self.parameters()?;
+        let body = self.function_body()?;
+        Ok(Stmt::Function { name, params, body })
+    }
+
+    /// Parses a parameter list up to and including the closing `)`; the
+    /// opening `(` has already been consumed.
+    fn parameters(&mut self) -> Result<Vec<Token>, ParseError> {
+        let mut params = Vec::new();
+        if !self.check(&TokenType::RightParen) {
+            loop {
+                if params.len() >= 255 {
+                    return Err(ParseError::Error(
+                        Box::new(self.peek().clone()),
+                        "Can't have more than 255 parameters.".to_string(),
+                    ));
+                }
+                params.push(self.consume(TokenType::Identifier, "Expect parameter name.")?);
+                if !self.match_token(&[TokenType::Comma]) {
+                    break;
+                }
+            }
+        }
+        self.consume(TokenType::RightParen, "Expect ')' after parameters.")?;
+        Ok(params)
+    }
+
+    fn function_body(&mut self) -> Result<Rc<Vec<Stmt>>, ParseError> {
+        self.consume(TokenType::LeftBrace, "Expect '{' before function body.")?;
+        Ok(Rc::new(self.block()?))
+    }
+
     fn var_declaration(&mut self) -> Result<Stmt, ParseError> {
         let name = self.consume(TokenType::Identifier, "Expect variable name.")?;
 
@@ -63,6 +161,9 @@ impl Parser {
         if self.match_token(&[TokenType::Print]) {
             return self.print_statement();
         }
+        if self.match_token(&[TokenType::Return]) {
+            return self.return_statement();
+        }
         if self.match_token(&[TokenType::While]) {
             return self.while_statement();
         }
@@ -150,7 +251,8 @@ impl Parser {
                     "true".to_string(),
                     None,
                     self.previous().line,
-                ),
+                )
+                .with_position(self.previous().column, self.previous().span),
             },
         };
 
@@ -178,6 +280,17 @@ impl Parser {
         Ok(statements)
     }
 
+    fn return_statement(&mut self) -> Result<Stmt, ParseError> {
+        let keyword = self.previous().clone();
+        let value = if self.check(&TokenType::Semicolon) {
+            None
+        } else {
+            Some(self.expression()?)
+        };
+        self.consume(TokenType::Semicolon, "Expect ';' after return value.")?;
+        Ok(Stmt::Return { keyword, value })
+    }
+
     fn print_statement(&mut self) -> Result<Stmt, ParseError> {
         let value = self.expression()?;
         self.consume(TokenType::Semicolon, "Expect ';' after value.")?;
@@ -195,25 +308,136 @@ impl Parser {
     }
 
     fn assignment(&mut self) -> Result<Expr, ParseError> {
-        let expr = self.or()?;
+        let expr = self.conditional()?;
 
         if self.match_token(&[TokenType::Equal]) {
             let equals = self.previous().clone();
             let value = self.assignment()?;
 
-            if let Expr::Variable { name } = expr {
-                return Ok(Expr::Assign {
-                    name,
-                    value: Box::new(value),
-                });
+            match expr {
+                Expr::Variable { name } => {
+                    return Ok(Expr::Assign {
+                        name,
+                        value: Box::new(value),
+                    });
+                }
+                Expr::Index {
+                    object,
+                    bracket,
+                    index,
+                } => {
+                    return Ok(Expr::IndexAssign {
+                        object,
+                        bracket,
+                        index,
+                        value: Box::new(value),
+                    });
+                }
+                Expr::Get { object, name } => {
+                    return Ok(Expr::Set {
+                        object,
+                        name,
+                        value: Box::new(value),
+                    });
+                }
+                _ => {}
             }
 
             return Err(ParseError::Error(
-                equals,
+                Box::new(equals),
                 "Invalid assignment target.".to_string(),
             ));
         }
 
+        if self.match_token(&[
+            TokenType::PlusEqual,
+            TokenType::MinusEqual,
+            TokenType::StarEqual,
+            TokenType::SlashEqual,
+            TokenType::PercentEqual,
+        ]) {
+            let operator = self.previous().clone();
+            let value = self.assignment()?;
+            if !Self::is_assignable(&expr) {
+                return Err(ParseError::Error(
+                    Box::new(operator),
+                    "Invalid assignment target.".to_string(),
+                ));
+            }
+            return Ok(Expr::CompoundAssign {
+                target: Box::new(expr),
+                operator,
+                value: Box::new(value),
+            });
+        }
+
+        Ok(expr)
+    }
+
+    /// Whether `expr` can appear on the left of a compound assignment or as
+    /// the operand of `++` and `--`.
+    fn is_assignable(expr: &Expr) -> bool {
+        matches!(
+            expr,
+            Expr::Variable { .. } | Expr::Index { .. } | Expr::Get { .. }
+        )
+    }
+
+    fn increment(
+        &mut self,
+        target: Expr,
+        operator: Token,
+        prefix: bool,
+    ) -> Result<Expr, ParseError> {
+        if !Self::is_assignable(&target) {
+            return Err(ParseError::Error(
+                Box::new(operator),
+                "Invalid increment target.".to_string(),
+            ));
+        }
+        Ok(Expr::Increment {
+            target: Box::new(target),
+            operator,
+            prefix,
+        })
+    }
+
+    /// `a ? b : c`, right-associative so `a ? b : c ? d : e` nests in the
+    /// else branch.
+    fn conditional(&mut self) -> Result<Expr, ParseError> {
+        let condition = self.coalesce()?;
+
+        if self.match_token(&[TokenType::Question]) {
+            let question = self.previous().clone();
+            let then_branch = self.assignment()?;
+            self.consume(
+                TokenType::Colon,
+                "Expect ':' after then branch of conditional expression.",
+            )?;
+            let else_branch = self.conditional()?;
+            return Ok(Expr::Conditional {
+                condition: Box::new(condition),
+                question,
+                then_branch: Box::new(then_branch),
+                else_branch: Box::new(else_branch),
+            });
+        }
+
+        Ok(condition)
+    }
+
+    fn coalesce(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.or()?;
+
+        while self.match_token(&[TokenType::QuestionQuestion]) {
+            let operator = self.previous().clone();
+            let right = self.or()?;
+            expr = Expr::Coalesce {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
+            };
+        }
         Ok(expr)
     }
 
@@ -248,46 +472,73 @@ impl Parser {
     }
 
     fn equality(&mut self) -> Result<Expr, ParseError> {
-        let mut expr = self.comparison()?;
-
-        while self.match_token(&[TokenType::BangEqual, TokenType::EqualEqual]) {
-            let operator = self.previous().clone();
-            let right = self.comparison()?;
-            expr = Expr::Binary {
-                left: Box::new(expr),
-                operator,
-                right: Box::new(right),
-            };
-        }
-        Ok(expr)
+        self.left_associative(
+            &[TokenType::BangEqual, TokenType::EqualEqual],
+            Self::comparison,
+        )
     }
 
     fn comparison(&mut self) -> Result<Expr, ParseError> {
-        let mut expr = self.term()?;
+        self.left_associative(
+            &[
+                TokenType::Greater,
+                TokenType::GreaterEqual,
+                TokenType::Less,
+                TokenType::LessEqual,
+            ],
+            Self::bitwise_or,
+        )
+    }
 
-        while self.match_token(&[
-            TokenType::Greater,
-            TokenType::GreaterEqual,
-            TokenType::Less,
-            TokenType::LessEqual,
-        ]) {
-            let operator = self.previous().clone();
-            let right = self.term()?;
-            expr = Expr::Binary {
-                left: Box::new(expr),
-                operator,
-                right: Box::new(right),
-            };
-        }
-        Ok(expr)
+    // The bitwise operators bind tighter than comparisons, so `x & 1 == 0`
+    // tests the low bit.
+    fn bitwise_or(&mut self) -> Result<Expr, ParseError> {
+        self.left_associative(&[TokenType::Pipe], Self::bitwise_xor)
+    }
+
+    fn bitwise_xor(&mut self) -> Result<Expr, ParseError> {
+        self.left_associative(&[TokenType::Caret], Self::bitwise_and)
+    }
+
+    fn bitwise_and(&mut self) -> Result<Expr, ParseError> {
+        self.left_associative(&[TokenType::Ampersand], Self::shift)
+    }
+
+    fn shift(&mut self) -> Result<Expr, ParseError> {
+        self.left_associative(
+            &[TokenType::LessLess, TokenType::GreaterGreater],
+            Self::term,
+        )
     }
 
     fn term(&mut self) -> Result<Expr, ParseError> {
-        let mut expr = self.factor()?;
+        self.left_associative(&[TokenType::Minus, TokenType::Plus], Self::factor)
+    }
 
-        while self.match_token(&[TokenType::Minus, TokenType::Plus]) {
+    fn factor(&mut self) -> Result<Expr, ParseError> {
+        self.left_associative(
+            &[
+                TokenType::Slash,
+                TokenType::Star,
+                TokenType::Percent,
+                TokenType::TildeSlash,
+            ],
+            Self::unary,
+        )
+    }
+
+    /// Parses a run of binary operators from `operators` that share one
+    /// precedence level and group to the left.
+    fn left_associative(
+        &mut self,
+        operators: &[TokenType],
+        operand: fn(&mut Self) -> Result<Expr, ParseError>,
+    ) -> Result<Expr, ParseError> {
+        let mut expr = operand(self)?;
+
+        while self.match_token(operators) {
             let operator = self.previous().clone();
-            let right = self.factor()?;
+            let right = operand(self)?;
             expr = Expr::Binary {
                 left: Box::new(expr),
                 operator,
@@ -297,31 +548,46 @@ impl Parser {
         Ok(expr)
     }
 
-    fn factor(&mut self) -> Result<Expr, ParseError> {
-        let mut expr = self.unary()?;
-
-        while self.match_token(&[TokenType::Slash, TokenType::Star]) {
+    fn unary(&mut self) -> Result<Expr, ParseError> {
+        if self.match_token(&[TokenType::Bang, TokenType::Minus, TokenType::Tilde]) {
             let operator = self.previous().clone();
             let right = self.unary()?;
-            expr = Expr::Binary {
-                left: Box::new(expr),
+            return Ok(Expr::Unary {
                 operator,
                 right: Box::new(right),
-            };
+            });
         }
-        Ok(expr)
+        if self.match_token(&[TokenType::PlusPlus, TokenType::MinusMinus]) {
+            let operator = self.previous().clone();
+            let target = self.unary()?;
+            return self.increment(target, operator, true);
+        }
+        self.power()
     }
 
-    fn unary(&mut self) -> Result<Expr, ParseError> {
-        if self.match_token(&[TokenType::Bang, TokenType::Minus]) {
+    /// `**` binds tighter than unary minus on its left, so `-2 ** 2` is -4,
+    /// and groups to the right; its right operand may itself be unary.
+    fn power(&mut self) -> Result<Expr, ParseError> {
+        let expr = self.postfix()?;
+        if self.match_token(&[TokenType::StarStar]) {
             let operator = self.previous().clone();
             let right = self.unary()?;
-            return Ok(Expr::Unary {
+            return Ok(Expr::Binary {
+                left: Box::new(expr),
                 operator,
                 right: Box::new(right),
             });
         }
-        self.call()
+        Ok(expr)
+    }
+
+    fn postfix(&mut self) -> Result<Expr, ParseError> {
+        let expr = self.call()?;
+        if self.match_token(&[TokenType::PlusPlus, TokenType::MinusMinus]) {
+            let operator = self.previous().clone();
+            return self.increment(expr, operator, false);
+        }
+        Ok(expr)
     }
 
     fn call(&mut self) -> Result<Expr, ParseError> {
@@ -330,6 +596,22 @@ impl Parser {
         loop {
             if self.match_token(&[TokenType::LeftParen]) {
                 expr = self.finish_call(expr)?;
+            } else if self.match_token(&[TokenType::Dot]) {
+                let name =
+                    self.consume(TokenType::Identifier, "Expect property name after '.'.")?;
+                expr = Expr::Get {
+                    object: Box::new(expr),
+                    name,
+                };
+            } else if self.match_token(&[TokenType::LeftBracket]) {
+                let bracket = self.previous().clone();
+                let index = self.expression()?;
+                self.consume(TokenType::RightBracket, "Expect ']' after index.")?;
+                expr = Expr::Index {
+                    object: Box::new(expr),
+                    bracket,
+                    index: Box::new(index),
+                };
             } else {
                 break;
             }
@@ -344,7 +626,7 @@ impl Parser {
             loop {
                 if arguments.len() >= 255 {
                     return Err(ParseError::Error(
-                        self.peek().clone(),
+                        Box::new(self.peek().clone()),
                         "Can't have more than 255 arguments.".to_string(),
                     ));
                 }
@@ -380,11 +662,35 @@ impl Parser {
             let token = self.previous().clone();
             return Ok(Expr::Literal { value: token });
         }
+        if self.match_token(&[TokenType::Interpolation]) {
+            return self.interpolation();
+        }
+        if self.match_token(&[TokenType::LeftBracket]) {
+            return self.list();
+        }
+        if self.match_token(&[TokenType::LeftBrace]) {
+            return self.map();
+        }
         if self.match_token(&[TokenType::Identifier]) {
             return Ok(Expr::Variable {
                 name: self.previous().clone(),
             });
         }
+        if self.match_token(&[TokenType::Fun]) {
+            let keyword = self.previous().clone();
+            self.consume(TokenType::LeftParen, "Expect '(' after 'fun'.")?;
+            let params = self.parameters()?;
+            let body = self.function_body()?;
+            return Ok(Expr::Function {
+                keyword,
+                params,
+                body,
+            });
+        }
+        if self.check(&TokenType::LeftParen) && self.is_arrow_function() {
+            self.advance();
+            return self.arrow_function();
+        }
         if self.match_token(&[TokenType::LeftParen]) {
             let expr = self.expression()?;
             self.consume(TokenType::RightParen, "Expect ')' after expression.")?;
@@ -393,16 +699,113 @@ impl Parser {
             });
         }
         Err(ParseError::Error(
-            self.peek().clone(),
+            Box::new(self.peek().clone()),
             "Expect expression.".to_string(),
         ))
     }
 
+    /// Parses the elements of a list literal after its `[`. A trailing comma
+    /// is allowed.
+    fn list(&mut self) -> Result<Expr, ParseError> {
+        let bracket = self.previous().clone();
+        let mut elements = Vec::new();
+        while !self.check(&TokenType::RightBracket) {
+            elements.push(self.expression()?);
+            if !self.match_token(&[TokenType::Comma]) {
+                break;
+            }
+        }
+        self.consume(TokenType::RightBracket, "Expect ']' after list elements.")?;
+        Ok(Expr::List { bracket, elements })
+    }
+
+    /// Parses the entries of a map literal after its `{`. Keys are
+    /// expressions that must evaluate to strings. A trailing comma is allowed.
+    fn map(&mut self) -> Result<Expr, ParseError> {
+        let brace = self.previous().clone();
+        let mut entries = Vec::new();
+        while !self.check(&TokenType::RightBrace) {
+            let key = self.expression()?;
+            self.consume(TokenType::Colon, "Expect ':' after map key.")?;
+            let value = self.expression()?;
+            entries.push((key, value));
+            if !self.match_token(&[TokenType::Comma]) {
+                break;
+            }
+        }
+        self.consume(TokenType::RightBrace, "Expect '}' after map entries.")?;
+        Ok(Expr::Map { brace, entries })
+    }
+
+    /// Looks past the `(` at the current token for `(a, b) =>`, which tells an
+    /// arrow function apart from a parenthesized expression.
+    fn is_arrow_function(&self) -> bool {
+        let mut index = self.current + 1;
+        let token_type = |index: usize| self.tokens.get(index).map(|token| token.token_type);
+        if token_type(index) != Some(TokenType::RightParen) {
+            loop {
+                if token_type(index) != Some(TokenType::Identifier) {
+                    return false;
+                }
+                index += 1;
+                match token_type(index) {
+                    Some(TokenType::Comma) => index += 1,
+                    Some(TokenType::RightParen) => break,
+                    _ => return false,
+                }
+            }
+        }
+        token_type(index + 1) == Some(TokenType::Arrow)
+    }
+
+    /// Parses `(params) => body`, where the body is a block or a single
+    /// expression whose value is returned.
+    fn arrow_function(&mut self) -> Result<Expr, ParseError> {
+        let params = self.parameters()?;
+        let keyword = self.consume(TokenType::Arrow, "Expect '=>' after parameters.")?;
+        let body = if self.check(&TokenType::LeftBrace) {
+            self.function_body()?
+        } else {
+            let value = self.expression()?;
+            Rc::new(vec![Stmt::Return {
+                keyword: keyword.clone(),
+                value: Some(value),
+            }])
+        };
+        Ok(Expr::Function {
+            keyword,
+            params,
+            body,
+        })
+    }
+
+    fn interpolation(&mut self) -> Result<Expr, ParseError> {
+        let mut parts = Vec::new();
+        loop {
+            parts.push(Expr::Literal {
+                value: self.previous().clone(),
+            });
+            parts.push(self.expression()?);
+            if !self.match_token(&[TokenType::Interpolation]) {
+                break;
+            }
+        }
+        let end = self.consume(
+            TokenType::String,
+            "Expect '}' after interpolated expression.",
+        )?;
+        parts.push(Expr::Literal { value: end });
+        Ok(Expr::Interpolation { parts })
+    }
+
     fn consume(&mut self, token_type: TokenType, message: &str) -> Result<Token, ParseError> {
         if self.check(&token_type) {
             return Ok(self.advance().clone());
         }
-        Err(ParseError::Error(self.peek().clone(), message.to_string()))
+        Err(ParseError::Error(
+            Box::new(self.peek().clone()),
+            message.to_string(),
+        ))
     }
 
     fn match_token(&mut self, types: &[TokenType]) -> bool {
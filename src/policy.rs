@@ -0,0 +1,97 @@
+//! What a script may do to the machine it runs on.
+
+use crate::interpreter::{RuntimeError, RuntimeErrorKind};
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+/// Controls the natives that reach outside the interpreter, such as file
+/// access. Violations are ordinary runtime errors.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum Policy {
+    /// Full access, as a script run from the command line expects.
+    #[default]
+    Allow,
+    /// No access at all.
+    Deny,
+    /// File access confined to a directory tree. Relative paths are resolved
+    /// against the root rather than the working directory. Other host access
+    /// is refused.
+    Sandbox(PathBuf),
+}
+
+impl Policy {
+    /// Checks access to the host beyond files, such as its environment.
+    /// Only [`Policy::Allow`] permits it; a sandbox confines files and
+    /// refuses everything else.
+    pub fn host_access(&self, what: &str) -> Result<(), RuntimeError> {
+        match self {
+            Policy::Allow => Ok(()),
+            Policy::Deny | Policy::Sandbox(_) => Err(denied(format!("{what} is not allowed."))),
+        }
+    }
+
+    /// Resolves a path a script asked for, or refuses it.
+    ///
+    /// In a sandbox the path is checked both as written, so `..` cannot climb
+    /// out, and after following symbolic links, so a link inside the root
+    /// cannot point elsewhere. The file itself need not exist yet, but its
+    /// directory must.
+    pub fn file_path(&self, path: &str) -> Result<PathBuf, RuntimeError> {
+        let root = match self {
+            Policy::Allow => return Ok(PathBuf::from(path)),
+            Policy::Deny => return Err(denied("File access is not allowed.".to_string())),
+            Policy::Sandbox(root) => root
+                .canonicalize()
+                .map_err(|err| io_error(&root.display().to_string(), err))?,
+        };
+        let outside = || denied(format!("Path '{path}' is outside the sandbox."));
+        let requested = normalize(&root.join(path)).ok_or_else(outside)?;
+        if !requested.starts_with(&root) {
+            return Err(outside());
+        }
+        let resolved = match requested.canonicalize() {
+            Ok(resolved) => resolved,
+            Err(_) => {
+                let (Some(parent), Some(name)) = (requested.parent(), requested.file_name()) else {
+                    return Err(outside());
+                };
+                parent
+                    .canonicalize()
+                    .map_err(|err| io_error(path, err))?
+                    .join(name)
+            }
+        };
+        if resolved.starts_with(&root) {
+            Ok(resolved)
+        } else {
+            Err(outside())
+        }
+    }
+}
+
+/// Removes `.` and `..` components without touching the file system.
+/// Returns `None` for a path that climbs above the file system root.
+fn normalize(path: &Path) -> Option<PathBuf> {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    return None;
+                }
+            }
+            other => normalized.push(other),
+        }
+    }
+    Some(normalized)
+}
+
+fn denied(message: String) -> RuntimeError {
+    RuntimeErrorKind::PermissionDenied(message).into()
+}
+
+/// An I/O failure on `path`, reported as a runtime error.
+pub(crate) fn io_error(path: &str, err: io::Error) -> RuntimeError {
+    RuntimeErrorKind::Io(format!("{path}: {err}")).into()
+}
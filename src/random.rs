@@ -0,0 +1,63 @@
+//! A small seedable pseudo-random number generator for `random()` and
+//! `randomInt()`. It is not suitable for cryptography.
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// SplitMix64: one 64-bit word of state, fast, and good enough for
+/// scripting. The same seed always produces the same sequence.
+#[derive(Debug, Clone)]
+pub struct Random {
+    state: u64,
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Self {
+        Random { state: seed }
+    }
+
+    /// A generator seeded differently on every run.
+    pub fn from_entropy() -> Self {
+        // `RandomState` is keyed randomly per process; mixing in the time
+        // keeps two generators in one process apart.
+        let mut hasher = RandomState::new().build_hasher();
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        hasher.write_u128(nanos);
+        Random::new(hasher.finish())
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A float in `[0, 1)` with 53 random bits, every value equally likely.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// An integer in `low..=high`, without the bias of a plain modulo.
+    /// `low` must not exceed `high`.
+    pub fn range(&mut self, low: i64, high: i64) -> i64 {
+        let span = high.wrapping_sub(low) as u64;
+        if span == u64::MAX {
+            return self.next_u64() as i64;
+        }
+        let count = span + 1;
+        // Reject draws from the incomplete block at the top of the range.
+        let limit = u64::MAX - (u64::MAX - count + 1) % count;
+        loop {
+            let draw = self.next_u64();
+            if draw <= limit {
+                return low.wrapping_add((draw % count) as i64);
+            }
+        }
+    }
+}
@@ -0,0 +1,278 @@
+use crate::diagnostic::Diagnostic;
+use crate::expr::Expr;
+use crate::stmt::Stmt;
+use crate::token::Token;
+use crate::token_type::TokenType;
+use std::collections::HashMap;
+use std::fmt;
+
+#[derive(Debug)]
+pub enum ResolveError {
+    Error(Token, String),
+}
+
+impl From<&ResolveError> for Diagnostic {
+    fn from(err: &ResolveError) -> Self {
+        match err {
+            ResolveError::Error(token, message) => {
+                Diagnostic::error("E0200", message.clone(), Some(token.span))
+            }
+        }
+    }
+}
+
+/// Code that runs but is probably a mistake. `check` reports warnings;
+/// running a script ignores them.
+#[derive(Debug)]
+pub enum ResolveWarning {
+    /// A variable declared again in the same block scope, which shadows
+    /// the first declaration.
+    Redeclared(Token),
+}
+
+impl From<&ResolveWarning> for Diagnostic {
+    fn from(warning: &ResolveWarning) -> Self {
+        match warning {
+            ResolveWarning::Redeclared(token) => Diagnostic::warning(
+                "W0200",
+                "Already a variable with this name in this scope.",
+                Some(token.span),
+            ),
+        }
+    }
+}
+
+impl fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResolveError::Error(token, message) => {
+                if token.token_type == TokenType::EOF {
+                    write!(f, "[line {}] Error at end: {}", token.line, message)
+                } else {
+                    write!(
+                        f,
+                        "[line {}] Error at '{}': {}",
+                        token.line, token.lexeme, message
+                    )
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FunctionType {
+    None,
+    Function,
+}
+
+/// Static checks run between parsing and interpretation.
+///
+/// Globals are left alone so the REPL can redefine them freely; only block
+/// and function scopes are tracked. Each scope maps a name to whether its
+/// initializer has finished.
+pub struct Resolver {
+    scopes: Vec<HashMap<String, bool>>,
+    errors: Vec<ResolveError>,
+    warnings: Vec<ResolveWarning>,
+    current_function: FunctionType,
+}
+
+impl Default for Resolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Resolver {
+    pub fn new() -> Self {
+        Resolver {
+            scopes: Vec::new(),
+            errors: Vec::new(),
+            warnings: Vec::new(),
+            current_function: FunctionType::None,
+        }
+    }
+
+    pub fn resolve(&mut self, statements: &[Stmt]) -> Result<(), Vec<ResolveError>> {
+        self.resolve_statements(statements);
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(std::mem::take(&mut self.errors))
+        }
+    }
+
+    /// The warnings found by [`resolve`](Self::resolve), in source order.
+    pub fn take_warnings(&mut self) -> Vec<ResolveWarning> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    fn resolve_statements(&mut self, statements: &[Stmt]) {
+        for statement in statements {
+            self.resolve_statement(statement);
+        }
+    }
+
+    fn resolve_statement(&mut self, statement: &Stmt) {
+        match statement {
+            Stmt::Expression { expression } | Stmt::Print { expression } => {
+                self.resolve_expr(expression)
+            }
+            Stmt::Block { statements } => {
+                self.scopes.push(HashMap::new());
+                self.resolve_statements(statements);
+                self.scopes.pop();
+            }
+            Stmt::Var { name, initializer } => {
+                self.declare(name);
+                if let Some(initializer) = initializer {
+                    self.resolve_expr(initializer);
+                }
+                self.define(name);
+            }
+            Stmt::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.resolve_expr(condition);
+                self.resolve_statement(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.resolve_statement(else_branch);
+                }
+            }
+            Stmt::While { condition, body } => {
+                self.resolve_expr(condition);
+                self.resolve_statement(body);
+            }
+            Stmt::Function { name, params, body } => {
+                // Defined before the body so the function can call itself.
+                self.declare(name);
+                self.define(name);
+                self.resolve_function(params, body);
+            }
+            Stmt::Return { keyword, value } => {
+                if self.current_function == FunctionType::None {
+                    self.error(keyword, "Can't return from top-level code.");
+                }
+                if let Some(value) = value {
+                    self.resolve_expr(value);
+                }
+            }
+        }
+    }
+
+    fn resolve_function(&mut self, params: &[Token], body: &[Stmt]) {
+        let enclosing = std::mem::replace(&mut self.current_function, FunctionType::Function);
+        self.scopes.push(HashMap::new());
+        for param in params {
+            self.declare(param);
+            self.define(param);
+        }
+        self.resolve_statements(body);
+        self.scopes.pop();
+        self.current_function = enclosing;
+    }
+
+    fn resolve_expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Binary { left, right, .. }
+            | Expr::Logical { left, right, .. }
+            | Expr::Coalesce { left, right, .. } => {
+                self.resolve_expr(left);
+                self.resolve_expr(right);
+            }
+            Expr::Conditional {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                self.resolve_expr(condition);
+                self.resolve_expr(then_branch);
+                self.resolve_expr(else_branch);
+            }
+            Expr::Grouping { expression } => self.resolve_expr(expression),
+            Expr::Literal { .. } => {}
+            Expr::Assign { value, .. } => self.resolve_expr(value),
+            Expr::CompoundAssign { target, value, .. } => {
+                self.resolve_expr(target);
+                self.resolve_expr(value);
+            }
+            Expr::Increment { target, .. } => self.resolve_expr(target),
+            Expr::Unary { right, .. } => self.resolve_expr(right),
+            Expr::Variable { name } => {
+                if let Some(scope) = self.scopes.last()
+                    && scope.get(&name.lexeme) == Some(&false)
+                {
+                    self.error(name, "Can't read local variable in its own initializer.");
+                }
+            }
+            Expr::Call {
+                callee, arguments, ..
+            } => {
+                self.resolve_expr(callee);
+                for argument in arguments {
+                    self.resolve_expr(argument);
+                }
+            }
+            Expr::List { elements, .. } => {
+                for element in elements {
+                    self.resolve_expr(element);
+                }
+            }
+            Expr::Index { object, index, .. } => {
+                self.resolve_expr(object);
+                self.resolve_expr(index);
+            }
+            Expr::Map { entries, .. } => {
+                for (key, value) in entries {
+                    self.resolve_expr(key);
+                    self.resolve_expr(value);
+                }
+            }
+            Expr::Get { object, .. } => self.resolve_expr(object),
+            Expr::Set { object, value, .. } => {
+                self.resolve_expr(object);
+                self.resolve_expr(value);
+            }
+            Expr::IndexAssign {
+                object,
+                index,
+                value,
+                ..
+            } => {
+                self.resolve_expr(object);
+                self.resolve_expr(index);
+                self.resolve_expr(value);
+            }
+            Expr::Interpolation { parts } => {
+                for part in parts {
+                    self.resolve_expr(part);
+                }
+            }
+            Expr::Function { params, body, .. } => self.resolve_function(params, body),
+        }
+    }
+
+    fn declare(&mut self, name: &Token) {
+        let Some(scope) = self.scopes.last_mut() else {
+            return;
+        };
+        if scope.insert(name.lexeme.clone(), false).is_some() {
+            self.warnings.push(ResolveWarning::Redeclared(name.clone()));
+        }
+    }
+
+    fn define(&mut self, name: &Token) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.lexeme.clone(), true);
+        }
+    }
+
+    fn error(&mut self, token: &Token, message: &str) {
+        self.errors
+            .push(ResolveError::Error(token.clone(), message.to_string()));
+    }
+}
@@ -1,51 +1,92 @@
 use std::collections::HashMap;
 use std::fmt;
 
+use crate::bigint::BigInt;
 use crate::cursor::Cursor;
+use crate::diagnostic::Diagnostic;
 use crate::token::Literal;
-use crate::token::Token;
+use crate::token::{Span, Token};
 use crate::token_type::TokenType;
 
 #[derive(Debug)]
 pub struct ScanError {
+    code: &'static str,
     line: usize,
+    column: usize,
+    span: Span,
     message: String,
 }
 
 impl ScanError {
-    fn new(line: usize, message: &str) -> Self {
+    fn new(code: &'static str, line: usize, column: usize, span: Span, message: &str) -> Self {
         ScanError {
+            code,
             line,
+            column,
+            span,
             message: message.to_string(),
         }
     }
 }
 
+impl From<&ScanError> for Diagnostic {
+    fn from(err: &ScanError) -> Self {
+        Diagnostic::error(err.code, err.message.clone(), Some(err.span))
+    }
+}
+
 impl fmt::Display for ScanError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "[line {}] Error: {}", self.line, self.message)
+        write!(
+            f,
+            "[line {}:{}] Error: {}",
+            self.line, self.column, self.message
+        )
     }
 }
 
 pub struct Scanner {
     source: Vec<char>,
+    /// Byte offset of every character, plus one for the end of the source.
+    offsets: Vec<usize>,
     tokens: Vec<Token>,
+    errors: Vec<ScanError>,
 
     start: usize,
     current: usize,
     line: usize,
+    line_start: usize,
+    start_line: usize,
+    start_column: usize,
+    keep_comments: bool,
+    source_id: usize,
+    /// One entry per `${` still open, counting the `{` nested inside it.
+    interpolations: Vec<usize>,
 
     keywords: HashMap<&'static str, TokenType>,
 }
 
 impl Scanner {
     pub fn new(source: &str) -> Self {
+        let offsets = source
+            .char_indices()
+            .map(|(offset, _)| offset)
+            .chain(std::iter::once(source.len()))
+            .collect();
         Scanner {
             source: source.chars().collect(),
+            offsets,
             tokens: Vec::new(),
+            errors: Vec::new(),
             start: 0,
             current: 0,
             line: 1,
+            line_start: 0,
+            start_line: 1,
+            start_column: 1,
+            keep_comments: false,
+            source_id: 0,
+            interpolations: Vec::new(),
             keywords: {
                 HashMap::from([
                     ("and", TokenType::And),
@@ -69,19 +110,51 @@ impl Scanner {
         }
     }
 
-    pub fn scan_tokens(&mut self) -> Result<Vec<Token>, ScanError> {
+    /// Emits `Comment` tokens instead of discarding comments. Only tools that
+    /// work on the raw token stream, such as the formatter, want these.
+    pub fn keep_comments(mut self, keep: bool) -> Self {
+        self.keep_comments = keep;
+        self
+    }
+
+    /// Marks every span with `source`, the id a session gave this script.
+    pub fn source_id(mut self, source: usize) -> Self {
+        self.source_id = source;
+        self
+    }
+
+    /// Scans the whole source. Lexical errors do not stop the scan: each one
+    /// is recorded and leaves an `Error` token behind, so callers get every
+    /// error in the file together with a token stream the parser can still use.
+    pub fn scan_tokens(&mut self) -> (Vec<Token>, Vec<ScanError>) {
         loop {
             if self.is_at_end() {
                 break;
             }
 
             self.start = self.current;
-            self.scan_token()?;
+            self.start_line = self.line;
+            self.start_column = self.start - self.line_start + 1;
+            if let Err(err) = self.scan_token() {
+                self.add_token(TokenType::Error, None);
+                self.errors.push(err);
+            }
         }
 
-        self.tokens
-            .push(Token::new(TokenType::EOF, String::new(), None, self.line));
-        Ok(std::mem::take(&mut self.tokens))
+        let end = *self.offsets.last().unwrap_or(&0);
+        let eof = Token::new(TokenType::EOF, String::new(), None, self.line)
+            .with_position(self.current - self.line_start + 1, Span::new(end, end));
+        self.tokens.push(eof);
+        for token in &mut self.tokens {
+            token.span.source = self.source_id;
+        }
+        for error in &mut self.errors {
+            error.span.source = self.source_id;
+        }
+        (
+            std::mem::take(&mut self.tokens),
+            std::mem::take(&mut self.errors),
+        )
     }
 
     fn scan_token(&mut self) -> Result<(), ScanError> {
@@ -89,14 +162,64 @@ impl Scanner {
         match c {
             '(' => self.add_token(TokenType::LeftParen, None),
             ')' => self.add_token(TokenType::RightParen, None),
-            '{' => self.add_token(TokenType::LeftBrace, None),
-            '}' => self.add_token(TokenType::RightBrace, None),
+            '{' => {
+                if let Some(depth) = self.interpolations.last_mut() {
+                    *depth += 1;
+                }
+                self.add_token(TokenType::LeftBrace, None)
+            }
+            '}' => match self.interpolations.last_mut() {
+                Some(0) => {
+                    // Closes a `${`: the string literal carries on from here.
+                    self.interpolations.pop();
+                    self.string()?
+                }
+                Some(depth) => {
+                    *depth -= 1;
+                    self.add_token(TokenType::RightBrace, None)
+                }
+                None => self.add_token(TokenType::RightBrace, None),
+            },
+            '[' => self.add_token(TokenType::LeftBracket, None),
+            ']' => self.add_token(TokenType::RightBracket, None),
             ',' => self.add_token(TokenType::Comma, None),
+            ':' => self.add_token(TokenType::Colon, None),
             '.' => self.add_token(TokenType::Dot, None),
-            '-' => self.add_token(TokenType::Minus, None),
-            '+' => self.add_token(TokenType::Plus, None),
+            '-' => {
+                if self.match_char('-') {
+                    self.add_token(TokenType::MinusMinus, None)
+                } else if self.match_char('=') {
+                    self.add_token(TokenType::MinusEqual, None)
+                } else {
+                    self.add_token(TokenType::Minus, None)
+                }
+            }
+            '+' => {
+                if self.match_char('+') {
+                    self.add_token(TokenType::PlusPlus, None)
+                } else if self.match_char('=') {
+                    self.add_token(TokenType::PlusEqual, None)
+                } else {
+                    self.add_token(TokenType::Plus, None)
+                }
+            }
             ';' => self.add_token(TokenType::Semicolon, None),
-            '*' => self.add_token(TokenType::Star, None),
+            '*' => {
+                if self.match_char('*') {
+                    self.add_token(TokenType::StarStar, None)
+                } else if self.match_char('=') {
+                    self.add_token(TokenType::StarEqual, None)
+                } else {
+                    self.add_token(TokenType::Star, None)
+                }
+            }
+            '%' => {
+                if self.match_char('=') {
+                    self.add_token(TokenType::PercentEqual, None)
+                } else {
+                    self.add_token(TokenType::Percent, None)
+                }
+            }
             '!' => {
                 if self.match_char('=') {
                     self.add_token(TokenType::BangEqual, None)
@@ -107,19 +230,42 @@ impl Scanner {
             '=' => {
                 if self.match_char('=') {
                     self.add_token(TokenType::EqualEqual, None)
+                } else if self.match_char('>') {
+                    self.add_token(TokenType::Arrow, None)
                 } else {
                     self.add_token(TokenType::Equal, None)
                 }
             }
+            '&' => self.add_token(TokenType::Ampersand, None),
+            '|' => self.add_token(TokenType::Pipe, None),
+            '^' => self.add_token(TokenType::Caret, None),
+            '~' => {
+                if self.match_char('/') {
+                    self.add_token(TokenType::TildeSlash, None)
+                } else {
+                    self.add_token(TokenType::Tilde, None)
+                }
+            }
             '>' => {
-                if self.match_char('=') {
+                if self.match_char('>') {
+                    self.add_token(TokenType::GreaterGreater, None)
+                } else if self.match_char('=') {
                     self.add_token(TokenType::GreaterEqual, None)
                 } else {
                     self.add_token(TokenType::Greater, None)
                 }
             }
+            '?' => {
+                if self.match_char('?') {
+                    self.add_token(TokenType::QuestionQuestion, None)
+                } else {
+                    self.add_token(TokenType::Question, None)
+                }
+            }
             '<' => {
-                if self.match_char('=') {
+                if self.match_char('<') {
+                    self.add_token(TokenType::LessLess, None)
+                } else if self.match_char('=') {
                     self.add_token(TokenType::LessEqual, None)
                 } else {
                     self.add_token(TokenType::Less, None)
@@ -131,6 +277,16 @@ impl Scanner {
                     while !self.is_at_end() && *self.peek() != '\n' {
                         self.advance();
                     }
+                    if self.keep_comments {
+                        self.add_token(TokenType::Comment, None);
+                    }
+                } else if self.match_char('*') {
+                    self.block_comment()?;
+                    if self.keep_comments {
+                        self.add_token(TokenType::Comment, None);
+                    }
+                } else if self.match_char('=') {
+                    self.add_token(TokenType::SlashEqual, None);
                 } else {
                     self.add_token(TokenType::Slash, None);
                 }
@@ -138,18 +294,22 @@ impl Scanner {
             ' ' | '\r' | '\t' => {
                 // Ignore whitespace.
             }
-            '\n' => {
-                self.line += 1;
-            }
+            '\n' => self.newline(),
             '\"' => self.string()?,
             '0'..='9' => {
-                self.number();
+                if let Err(err) = self.number() {
+                    // The lexeme is still a number as far as the parser is
+                    // concerned; a placeholder avoids a cascade of errors.
+                    self.errors.push(err);
+                    self.add_token(TokenType::Number, Some(Literal::Int(0)));
+                }
             }
+            'r' if self.is_raw_string_start() => self.raw_string()?,
             'A'..='Z' | 'a'..='z' | '_' => {
                 self.identifier();
             }
             _ => {
-                return Err(ScanError::new(self.line, "Unexpected character"));
+                return Err(self.error("E0001", "Unexpected character."));
             }
         }
         Ok(())
@@ -157,10 +317,43 @@ impl Scanner {
 
     fn add_token(&mut self, token_type: TokenType, literal: Option<Literal>) {
         let text: String = self.source[self.start..self.current].iter().collect();
-        let token = Token::new(token_type, text, literal, self.line);
+        let token = Token::new(token_type, text, literal, self.start_line)
+            .with_position(self.start_column, self.span());
         self.tokens.push(token);
     }
 
+    fn span(&self) -> Span {
+        Span::new(self.offsets[self.start], self.offsets[self.current])
+    }
+
+    fn newline(&mut self) {
+        self.line += 1;
+        self.line_start = self.current;
+    }
+
+    /// Builds an error pointing at the lexeme scanned so far.
+    fn error(&self, code: &'static str, message: &str) -> ScanError {
+        ScanError::new(
+            code,
+            self.start_line,
+            self.start_column,
+            self.span(),
+            message,
+        )
+    }
+
+    /// Builds an error pointing from the character at `from`, on the current
+    /// line, to the current position.
+    fn error_from(&self, from: usize, code: &'static str, message: &str) -> ScanError {
+        ScanError::new(
+            code,
+            self.line,
+            from - self.line_start + 1,
+            Span::new(self.offsets[from], self.offsets[self.current]),
+            message,
+        )
+    }
+
     fn match_char(&mut self, expected: char) -> bool {
         if self.is_at_end() {
             return false;
@@ -172,43 +365,283 @@ impl Scanner {
         true
     }
 
+    /// Skips a `/* ... */` comment. Block comments nest, so every `/*` inside
+    /// needs its own `*/`.
+    fn block_comment(&mut self) -> Result<(), ScanError> {
+        let mut depth = 1;
+        while depth > 0 {
+            if self.is_at_end() {
+                // Point at the opening `/*` rather than the end of the file.
+                return Err(ScanError::new(
+                    "E0005",
+                    self.start_line,
+                    self.start_column,
+                    Span::new(self.offsets[self.start], self.offsets[self.start + 2]),
+                    "Unterminated block comment.",
+                ));
+            }
+            let c = *self.advance();
+            match c {
+                '\n' => self.newline(),
+                '/' if self.match_char('*') => depth += 1,
+                '*' if self.match_char('/') => depth -= 1,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Scans the rest of a string literal. A `${` ends the current segment
+    /// with an `Interpolation` token; the scanner then returns to ordinary
+    /// tokens until the matching `}`, which resumes the string. The last
+    /// segment is a plain `String` token.
     fn string(&mut self) -> Result<(), ScanError> {
+        let mut value = String::new();
         loop {
             if self.is_at_end() || *self.peek() == '\"' {
                 break;
             }
-            if *self.peek() == '\n' {
-                self.line += 1;
+            if *self.peek() == '$' && self.peek_next() == Some(&'{') {
+                self.current += 2;
+                self.interpolations.push(0);
+                self.add_token(TokenType::Interpolation, Some(Literal::Str(value)));
+                return Ok(());
+            }
+            match *self.advance() {
+                '\\' => match self.escape() {
+                    Ok(c) => value.push(c),
+                    // Keep going so the rest of the string is still checked.
+                    Err(err) => self.errors.push(err),
+                },
+                '\n' => {
+                    self.newline();
+                    value.push('\n');
+                }
+                c => value.push(c),
             }
-            self.advance();
         }
         if self.is_at_end() {
-            return Err(ScanError::new(self.line, "Unterminated string."));
+            return Err(self.error("E0002", "Unterminated string."));
         }
         self.advance();
-        let value: String = self.source[self.start + 1..self.current - 1]
-            .iter()
-            .collect();
         self.add_token(TokenType::String, Some(Literal::Str(value)));
         Ok(())
     }
 
-    fn number(&mut self) {
-        while !self.is_at_end() && self.peek().is_ascii_digit() {
+    /// Decodes the escape sequence after a backslash.
+    fn escape(&mut self) -> Result<char, ScanError> {
+        let backslash = self.current - 1;
+        if self.is_at_end() {
+            // Reported as an unterminated string.
+            return Ok('\\');
+        }
+        match *self.advance() {
+            'n' => Ok('\n'),
+            't' => Ok('\t'),
+            'r' => Ok('\r'),
+            '0' => Ok('\0'),
+            '\\' => Ok('\\'),
+            '"' => Ok('"'),
+            '\'' => Ok('\''),
+            '$' => Ok('$'),
+            'u' => self.unicode_escape(backslash),
+            '\n' => {
+                let err = self.error_from(backslash, "E0004", "Unknown escape sequence.");
+                self.newline();
+                Err(err)
+            }
+            c => Err(self.error_from(
+                backslash,
+                "E0004",
+                &format!("Unknown escape sequence '\\{c}'."),
+            )),
+        }
+    }
+
+    /// Decodes `\u{XXXX}` with one to six hex digits naming a unicode scalar.
+    fn unicode_escape(&mut self, backslash: usize) -> Result<char, ScanError> {
+        if !self.match_char('{') {
+            return Err(self.error_from(backslash, "E0004", "Expect '{' after '\\u'."));
+        }
+        let digits_start = self.current;
+        while self.peek_is(|c| c.is_ascii_hexdigit()) {
             self.advance();
         }
-        if !self.is_at_end()
-            && *self.peek() == '.'
+        let digits: String = self.source[digits_start..self.current].iter().collect();
+        if !self.match_char('}') {
+            return Err(self.error_from(
+                backslash,
+                "E0004",
+                "Expect '}' to close the unicode escape.",
+            ));
+        }
+        if digits.is_empty() || digits.len() > 6 {
+            return Err(self.error_from(
+                backslash,
+                "E0004",
+                "A unicode escape needs one to six hex digits.",
+            ));
+        }
+        u32::from_str_radix(&digits, 16)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or_else(|| {
+                self.error_from(
+                    backslash,
+                    "E0004",
+                    &format!("'{digits}' is not a unicode scalar value."),
+                )
+            })
+    }
+
+    /// Whether the `r` just scanned opens a raw string: `r"..."` or
+    /// `r#"..."#` with any number of matching hashes.
+    fn is_raw_string_start(&self) -> bool {
+        self.source[self.current..]
+            .iter()
+            .find(|c| **c != '#')
+            .is_some_and(|c| *c == '"')
+    }
+
+    /// Scans a raw string, which has no escapes and may contain `"` when the
+    /// delimiters use hashes.
+    fn raw_string(&mut self) -> Result<(), ScanError> {
+        let mut hashes = 0;
+        while self.match_char('#') {
+            hashes += 1;
+        }
+        self.advance();
+        let content_start = self.current;
+        loop {
+            if self.is_at_end() {
+                return Err(self.error("E0002", "Unterminated raw string."));
+            }
+            let c = *self.advance();
+            if c == '\n' {
+                self.newline();
+            } else if c == '"'
+                && self.source[self.current..]
+                    .iter()
+                    .take(hashes)
+                    .filter(|c| **c == '#')
+                    .count()
+                    == hashes
+            {
+                let value: String = self.source[content_start..self.current - 1]
+                    .iter()
+                    .collect();
+                self.current += hashes;
+                self.add_token(TokenType::String, Some(Literal::Str(value)));
+                return Ok(());
+            }
+        }
+    }
+
+    /// Scans a number literal: decimal with an optional fraction and
+    /// exponent, or `0x`, `0o` and `0b` integers. Digits may be grouped with
+    /// `_`, which must sit between two digits.
+    fn number(&mut self) -> Result<(), ScanError> {
+        let radix = match (*self.previous(), self.peek_char()) {
+            ('0', Some('x' | 'X')) => 16,
+            ('0', Some('o' | 'O')) => 8,
+            ('0', Some('b' | 'B')) => 2,
+            _ => 10,
+        };
+        if radix != 10 {
+            self.advance();
+            let digits_start = self.current;
+            self.digits(radix);
+            let no_digits = self.current == digits_start;
+            self.reject_trailing_word()?;
+            if no_digits {
+                return Err(self.error("E0003", "Expect digits after the radix prefix."));
+            }
+            self.check_underscores(radix)?;
+            let digits: String = self.source[digits_start..self.current].iter().collect();
+            self.add_token(TokenType::Number, Some(integer_literal(&digits, radix)));
+            return Ok(());
+        }
+
+        let mut is_integer = true;
+        self.digits(10);
+        if self.peek_is(|c| c == '.')
             && matches!(self.peek_next(), Some(next) if next.is_ascii_digit())
         {
+            is_integer = false;
             self.advance();
-            while !self.is_at_end() && self.peek().is_ascii_digit() {
+            self.digits(10);
+        }
+        if self.peek_is(|c| c == 'e' || c == 'E') {
+            is_integer = false;
+            self.advance();
+            if self.peek_is(|c| c == '+' || c == '-') {
                 self.advance();
             }
+            if !self.peek_is(|c| c.is_ascii_digit()) {
+                self.reject_trailing_word()?;
+                return Err(self.error("E0003", "Expect digits in the exponent."));
+            }
+            self.digits(10);
+        }
+        self.reject_trailing_word()?;
+        self.check_underscores(10)?;
+
+        let text: String = self.source[self.start..self.current]
+            .iter()
+            .filter(|c| **c != '_')
+            .collect();
+        if is_integer {
+            self.add_token(TokenType::Number, Some(integer_literal(&text, 10)));
+            return Ok(());
+        }
+        let value = text
+            .parse()
+            .map_err(|_| self.error("E0003", "Invalid number literal."))?;
+        self.add_token(TokenType::Number, Some(Literal::Number(value)));
+        Ok(())
+    }
+
+    fn digits(&mut self, radix: u32) {
+        while self.peek_is(|c| c.is_digit(radix) || c == '_') {
+            self.advance();
+        }
+    }
+
+    /// Rejects letters or digits glued to the end of a number, such as the
+    /// `2` in `0b102`, instead of silently starting a new token.
+    fn reject_trailing_word(&mut self) -> Result<(), ScanError> {
+        if !self.peek_is(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Ok(());
+        }
+        while self.peek_is(|c| c.is_ascii_alphanumeric() || c == '_') {
+            self.advance();
+        }
+        Err(self.error("E0003", "Invalid digit in number literal."))
+    }
+
+    fn check_underscores(&self, radix: u32) -> Result<(), ScanError> {
+        let text = &self.source[self.start..self.current];
+        for (index, c) in text.iter().enumerate() {
+            let between_digits = index > 0
+                && text[index - 1].is_digit(radix)
+                && text.get(index + 1).is_some_and(|next| next.is_digit(radix));
+            if *c == '_' && !between_digits {
+                return Err(self.error("E0003", "'_' must separate two digits."));
+            }
+        }
+        Ok(())
+    }
+
+    fn peek_is(&self, predicate: impl Fn(char) -> bool) -> bool {
+        !self.is_at_end() && predicate(*self.peek())
+    }
+
+    fn peek_char(&self) -> Option<char> {
+        if self.is_at_end() {
+            None
+        } else {
+            Some(*self.peek())
         }
-        let value: String = self.source[self.start..self.current].iter().collect();
-        let number_value: f64 = value.parse().unwrap();
-        self.add_token(TokenType::Number, Some(Literal::Number(number_value)));
     }
 
     fn identifier(&mut self) {
@@ -217,7 +650,7 @@ impl Scanner {
         }
         let text: String = self.source[self.start..self.current].iter().collect();
         let token_type: TokenType = match self.keywords.get(text.as_str()) {
-            Some(token_type) => token_type.clone(),
+            Some(token_type) => *token_type,
             None => TokenType::Identifier,
         };
         self.add_token(token_type, None);
@@ -241,3 +674,13 @@ impl Cursor<char> for Scanner {
         self.current >= self.source.len()
     }
 }
+
+/// An integer literal from already validated digits, as an `Int` when it
+/// fits and a `BigInt` otherwise.
+fn integer_literal(digits: &str, radix: u32) -> Literal {
+    let digits = digits.replace('_', "");
+    match i64::from_str_radix(&digits, radix) {
+        Ok(value) => Literal::Int(value),
+        Err(_) => Literal::BigInt(BigInt::parse(&digits, radix).unwrap_or_else(BigInt::zero)),
+    }
+}
@@ -0,0 +1,76 @@
+use super::{NativeModule, type_error};
+use crate::bigint::BigInt;
+use crate::interpreter::{self, Interpreter, RuntimeError};
+use crate::value::Value;
+
+pub fn module() -> NativeModule {
+    NativeModule::new("core")
+        .function("clock", 0, clock)
+        .function("str", 1, str)
+        .function("num", 1, num)
+        .function("bool", 1, bool)
+        .function("type", 1, type_of)
+        .function("arity", 1, arity)
+}
+
+fn clock(interpreter: &mut Interpreter, _arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    Ok(Value::Number(interpreter.time_source().now()))
+}
+
+/// The text `print` would show for the value.
+fn str(_interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    Ok(Value::Str(arguments[0].to_string()))
+}
+
+/// Parses a decimal number, returning `nil` when the text is not one so
+/// scripts can write `num(text) ?? 0`. Numbers are returned unchanged.
+fn num(_interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    match &arguments[0] {
+        Value::Str(text) => Ok(parse_number(text.trim()).unwrap_or(Value::Nil)),
+        number @ (Value::Number(_) | Value::Int(_) | Value::BigInt(_)) => Ok(number.clone()),
+        _ => Err(type_error("num", "a string or a number")),
+    }
+}
+
+/// Integers parse exactly, like integer literals; anything with a fraction
+/// or exponent becomes a float. Words such as `inf` and `NaN`, which Rust
+/// would accept, are rejected because Lox has no literal for them, and so
+/// are exponents too large for a float, such as `1e400`.
+fn parse_number(text: &str) -> Option<Value> {
+    let digits = text.strip_prefix(['-', '+']).unwrap_or(text);
+    if !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit()) {
+        let magnitude = BigInt::parse(digits, 10)?;
+        let value = if text.starts_with('-') {
+            -&magnitude
+        } else {
+            magnitude
+        };
+        return Some(interpreter::normalize(value));
+    }
+    let is_decimal = digits
+        .chars()
+        .all(|c| c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E' | '+' | '-'));
+    if !is_decimal || !digits.starts_with(|c: char| c.is_ascii_digit() || c == '.') {
+        return None;
+    }
+    text.parse()
+        .ok()
+        .filter(|number: &f64| number.is_finite())
+        .map(Value::Number)
+}
+
+fn bool(_interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    Ok(Value::Boolean(arguments[0].is_truthy()))
+}
+
+fn type_of(_interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    Ok(Value::Str(arguments[0].type_name().to_string()))
+}
+
+/// The fewest arguments a function accepts.
+fn arity(_interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    match &arguments[0] {
+        Value::Callable(callable) => Ok(Value::Int(callable.arity() as i64)),
+        _ => Err(type_error("arity", "a function")),
+    }
+}
@@ -0,0 +1,92 @@
+//! Text file access. Every path goes through the interpreter's [`Policy`]
+//! first, so a denied or sandboxed script gets a runtime error instead of
+//! touching the file.
+//!
+//! [`Policy`]: crate::policy::Policy
+
+use super::{NativeModule, string_arg};
+use crate::interpreter::{Interpreter, RuntimeError};
+use crate::policy::io_error;
+use crate::value::Value;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+
+pub fn module() -> NativeModule {
+    NativeModule::new("file")
+        .function("readFile", 1, read_file)
+        .function("writeFile", 2, write_file)
+        .function("appendFile", 2, append_file)
+        .function("readLines", 1, read_lines)
+        .function("exists", 1, exists)
+        .function("listDir", 1, list_dir)
+}
+
+/// The path argument, resolved and checked against the policy.
+fn path_arg<'a>(
+    interpreter: &Interpreter,
+    function: &str,
+    arguments: &'a [Value],
+) -> Result<(&'a str, PathBuf), RuntimeError> {
+    let path = string_arg(function, arguments, 0)?;
+    Ok((path, interpreter.policy().file_path(path)?))
+}
+
+fn read_file(interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    let (path, resolved) = path_arg(interpreter, "readFile", &arguments)?;
+    let text = fs::read_to_string(resolved).map_err(|err| io_error(path, err))?;
+    Ok(Value::Str(text))
+}
+
+/// Replaces the file's contents, creating it if needed.
+fn write_file(interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    let (path, resolved) = path_arg(interpreter, "writeFile", &arguments)?;
+    let text = string_arg("writeFile", &arguments, 1)?;
+    fs::write(resolved, text).map_err(|err| io_error(path, err))?;
+    Ok(Value::Nil)
+}
+
+fn append_file(
+    interpreter: &mut Interpreter,
+    arguments: Vec<Value>,
+) -> Result<Value, RuntimeError> {
+    let (path, resolved) = path_arg(interpreter, "appendFile", &arguments)?;
+    let text = string_arg("appendFile", &arguments, 1)?;
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(resolved)
+        .and_then(|mut file| file.write_all(text.as_bytes()))
+        .map_err(|err| io_error(path, err))?;
+    Ok(Value::Nil)
+}
+
+/// The file's lines without their line endings.
+fn read_lines(interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    let (path, resolved) = path_arg(interpreter, "readLines", &arguments)?;
+    let text = fs::read_to_string(resolved).map_err(|err| io_error(path, err))?;
+    Ok(Value::list(
+        text.lines()
+            .map(|line| Value::Str(line.to_string()))
+            .collect(),
+    ))
+}
+
+fn exists(interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    let (_, resolved) = path_arg(interpreter, "exists", &arguments)?;
+    Ok(Value::Boolean(resolved.exists()))
+}
+
+/// The names of the entries in a directory, sorted.
+fn list_dir(interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    let (path, resolved) = path_arg(interpreter, "listDir", &arguments)?;
+    let mut names = fs::read_dir(resolved)
+        .and_then(|entries| {
+            entries
+                .map(|entry| entry.map(|entry| entry.file_name().to_string_lossy().into_owned()))
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .map_err(|err| io_error(path, err))?;
+    names.sort();
+    Ok(Value::list(names.into_iter().map(Value::Str).collect()))
+}
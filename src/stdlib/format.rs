@@ -0,0 +1,225 @@
+//! `format(template, ...)`: Rust-style `{}` placeholders with width,
+//! precision and alignment, such as `format("{:>8.2}", x)`.
+//!
+//! A placeholder is `{field:spec}`. The field is empty for the next
+//! argument, a number for an argument by position, or a name looked up in a
+//! map passed as the last argument. The spec is
+//! `[[fill]align][+][0][width][.precision][type]`, where align is `<`, `^` or
+//! `>` and type is one of `f`, `e`, `x`, `X`, `o`, `b` or `s`. `{{` and `}}`
+//! stand for literal braces.
+
+use super::{NativeModule, invalid_argument, string_arg};
+use crate::interpreter::{self, Interpreter, RuntimeError};
+use crate::value::Value;
+
+pub fn module() -> NativeModule {
+    NativeModule::new("format").function_with_arity("format", 1, None, format)
+}
+
+fn format(_interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    let template = string_arg("format", &arguments, 0)?;
+    render(template, &arguments[1..])
+        .map(Value::Str)
+        .map_err(|message| invalid_argument(format!("format() {message}.")))
+}
+
+fn render(template: &str, values: &[Value]) -> Result<String, String> {
+    let mut out = String::new();
+    let mut chars = template.chars().peekable();
+    let mut next_positional = 0;
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                out.push('{');
+            }
+            '{' => {
+                let mut placeholder = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some('{') | None => return Err("has an unclosed '{'".to_string()),
+                        Some(c) => placeholder.push(c),
+                    }
+                }
+                let (field, spec) = placeholder.split_once(':').unwrap_or((&placeholder, ""));
+                let value = lookup(field, values, &mut next_positional)?;
+                out.push_str(&Spec::parse(spec)?.apply(&value)?);
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                out.push('}');
+            }
+            '}' => return Err("has a '}' without a matching '{'".to_string()),
+            c => out.push(c),
+        }
+    }
+    Ok(out)
+}
+
+/// Finds the argument a placeholder's field refers to.
+fn lookup(field: &str, values: &[Value], next_positional: &mut usize) -> Result<Value, String> {
+    let position = if field.is_empty() {
+        *next_positional += 1;
+        *next_positional - 1
+    } else if let Ok(position) = field.parse::<usize>() {
+        position
+    } else {
+        let Some(Value::Map(map)) = values.last() else {
+            return Err(format!(
+                "needs a map as its last argument for the named field '{field}'"
+            ));
+        };
+        return map
+            .borrow()
+            .get(field)
+            .cloned()
+            .ok_or_else(|| format!("has no named argument '{field}'"));
+    };
+    values
+        .get(position)
+        .cloned()
+        .ok_or_else(|| format!("has no argument {position}"))
+}
+
+/// The largest width or precision, the same limit Rust's own formatter
+/// has. Anything bigger would not fit in memory anyway.
+const MAX_WIDTH: usize = u16::MAX as usize;
+
+#[derive(Debug, Default)]
+struct Spec {
+    fill: Option<char>,
+    align: Option<char>,
+    plus: bool,
+    zero: bool,
+    width: usize,
+    precision: Option<usize>,
+    kind: Option<char>,
+}
+
+impl Spec {
+    fn parse(spec: &str) -> Result<Spec, String> {
+        let chars: Vec<char> = spec.chars().collect();
+        let mut parsed = Spec::default();
+        let mut i = 0;
+        let is_align = |c: Option<&char>| matches!(c, Some('<' | '^' | '>'));
+        if is_align(chars.get(1)) {
+            parsed.fill = Some(chars[0]);
+            parsed.align = Some(chars[1]);
+            i = 2;
+        } else if is_align(chars.first()) {
+            parsed.align = Some(chars[0]);
+            i = 1;
+        }
+        if chars.get(i) == Some(&'+') {
+            parsed.plus = true;
+            i += 1;
+        }
+        if chars.get(i) == Some(&'0') {
+            parsed.zero = true;
+            i += 1;
+        }
+        let (width, end) = number(&chars, i);
+        parsed.width = width.unwrap_or(0);
+        i = end;
+        if chars.get(i) == Some(&'.') {
+            let (precision, end) = number(&chars, i + 1);
+            if precision.is_none() {
+                return Err(format!("has a '.' without a precision in '{spec}'"));
+            }
+            parsed.precision = precision;
+            i = end;
+        }
+        if let Some(kind) = chars.get(i) {
+            if !matches!(kind, 'f' | 'e' | 'x' | 'X' | 'o' | 'b' | 's') {
+                return Err(format!("has an unknown format type '{kind}' in '{spec}'"));
+            }
+            parsed.kind = Some(*kind);
+            i += 1;
+        }
+        let too_wide = parsed.width > MAX_WIDTH || parsed.precision.is_some_and(|p| p > MAX_WIDTH);
+        if i < chars.len() || too_wide {
+            return Err(format!("has an invalid format spec '{spec}'"));
+        }
+        Ok(parsed)
+    }
+
+    fn apply(&self, value: &Value) -> Result<String, String> {
+        let is_number = matches!(value, Value::Number(_) | Value::Int(_) | Value::BigInt(_));
+        let mut text = match self.kind {
+            Some(kind @ ('x' | 'X' | 'o' | 'b')) => {
+                let Value::Int(number) = value else {
+                    return Err(format!("type '{kind}' needs a 64-bit integer"));
+                };
+                let digits = match kind {
+                    'x' => format!("{:x}", number.unsigned_abs()),
+                    'X' => format!("{:X}", number.unsigned_abs()),
+                    'o' => format!("{:o}", number.unsigned_abs()),
+                    _ => format!("{:b}", number.unsigned_abs()),
+                };
+                if *number < 0 {
+                    format!("-{digits}")
+                } else {
+                    digits
+                }
+            }
+            Some(kind @ ('f' | 'e')) => {
+                let Some(number) = interpreter::as_float(value) else {
+                    return Err(format!("type '{kind}' needs a number"));
+                };
+                match (kind, self.precision) {
+                    ('f', precision) => format!("{:.*}", precision.unwrap_or(6), number),
+                    (_, Some(precision)) => format!("{:.*e}", precision, number),
+                    (_, None) => format!("{:e}", number),
+                }
+            }
+            _ => match (interpreter::as_float(value), self.precision) {
+                (Some(number), Some(precision)) => format!("{:.*}", precision, number),
+                (_, Some(precision)) => value.to_string().chars().take(precision).collect(),
+                (_, None) => value.to_string(),
+            },
+        };
+
+        if is_number && self.plus && !text.starts_with('-') {
+            text.insert(0, '+');
+        }
+        let length = text.chars().count();
+        if length >= self.width {
+            return Ok(text);
+        }
+        let padding = self.width - length;
+        if is_number && self.zero && self.align.is_none() {
+            let sign_length = usize::from(text.starts_with(['+', '-']));
+            text.insert_str(sign_length, &"0".repeat(padding));
+            return Ok(text);
+        }
+        let fill = self.fill.unwrap_or(' ').to_string();
+        let align = self.align.unwrap_or(if is_number { '>' } else { '<' });
+        let (before, after) = match align {
+            '<' => (0, padding),
+            '^' => (padding / 2, padding - padding / 2),
+            _ => (padding, 0),
+        };
+        Ok(format!(
+            "{}{}{}",
+            fill.repeat(before),
+            text,
+            fill.repeat(after)
+        ))
+    }
+}
+
+/// Parses decimal digits starting at `start`, returning the number, if any,
+/// and the position after it. Digits too big for a `usize` give `usize::MAX`.
+fn number(chars: &[char], start: usize) -> (Option<usize>, usize) {
+    let end = start
+        + chars[start.min(chars.len())..]
+            .iter()
+            .take_while(|c| c.is_ascii_digit())
+            .count();
+    let digits: String = chars[start.min(chars.len())..end].iter().collect();
+    if digits.is_empty() {
+        return (None, end);
+    }
+    (Some(digits.parse().unwrap_or(usize::MAX)), end)
+}
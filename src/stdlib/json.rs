@@ -0,0 +1,52 @@
+//! `json.parse` and `json.stringify`, reached through a single `json` global
+//! so the generic names do not crowd the global scope.
+
+use super::{NativeModule, invalid_argument, string_arg, type_error};
+use crate::interpreter::{Interpreter, RuntimeError};
+use crate::json;
+use crate::value::Value;
+
+pub fn module() -> NativeModule {
+    let members = NativeModule::new("json")
+        .function("parse", 1, parse)
+        .function_with_arity("stringify", 1, Some(2), stringify);
+    NativeModule::new("json").constant("json", members.to_map())
+}
+
+fn parse(_interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    let text = string_arg("json.parse", &arguments, 0)?;
+    json::parse(text).map_err(|err| invalid_argument(format!("json.parse(): {err}.")))
+}
+
+/// The most spaces an indent can have, the same limit as JavaScript's
+/// `JSON.stringify`.
+const MAX_INDENT: i64 = 10;
+
+/// Serializes a value; the optional indent is a number of spaces, up to
+/// ten, or the string to indent with, and pretty-prints the output.
+fn stringify(_interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    let indent = match arguments.get(1) {
+        None | Some(Value::Nil) => String::new(),
+        Some(Value::Str(indent)) => indent.clone(),
+        Some(spaces @ (Value::Int(_) | Value::Number(_))) => " ".repeat(indent_spaces(spaces)?),
+        Some(_) => return Err(type_error("json.stringify", "a number or a string indent")),
+    };
+    json::stringify(&arguments[0], &indent)
+        .map(Value::Str)
+        .map_err(|err| invalid_argument(format!("json.stringify() {err}.")))
+}
+
+fn indent_spaces(value: &Value) -> Result<usize, RuntimeError> {
+    let spaces = match value {
+        Value::Int(spaces) => Some(*spaces),
+        Value::Number(spaces) if spaces.fract() == 0.0 => Some(*spaces as i64),
+        _ => None,
+    };
+    match spaces {
+        Some(spaces @ 0..=MAX_INDENT) => Ok(spaces as usize),
+        Some(_) => Err(invalid_argument(format!(
+            "json.stringify() indent {value} is not between 0 and {MAX_INDENT}."
+        ))),
+        None => Err(type_error("json.stringify", "a whole number of spaces")),
+    }
+}
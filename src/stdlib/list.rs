@@ -0,0 +1,205 @@
+//! List functions, most of which take a Lox function and call it for each
+//! element. They work on a copy of the list, so a callback that changes the
+//! list does not affect the pass in progress, and they return new lists
+//! rather than changing their argument.
+
+use super::{NativeModule, invalid_argument, list_arg, type_error};
+use crate::interpreter::{Interpreter, RuntimeError};
+use crate::value::Value;
+use std::cmp::Ordering;
+
+pub fn module() -> NativeModule {
+    NativeModule::new("list")
+        .function("map", 2, map)
+        .function("filter", 2, filter)
+        .function_with_arity("reduce", 2, Some(3), reduce)
+        .function("any", 2, any)
+        .function("all", 2, all)
+        .function("find", 2, find)
+        .function_with_arity("sort", 1, Some(2), sort)
+        .function("reverse", 1, reverse)
+        .function("zip", 2, zip)
+        .function("enumerate", 1, enumerate)
+}
+
+/// A copy of the list argument at `index`, taken so callbacks can change the
+/// original without disturbing the iteration.
+fn elements(function: &str, arguments: &[Value], index: usize) -> Result<Vec<Value>, RuntimeError> {
+    Ok(list_arg(function, arguments, index)?.borrow().clone())
+}
+
+fn function_arg<'a>(
+    function: &str,
+    arguments: &'a [Value],
+    index: usize,
+) -> Result<&'a Value, RuntimeError> {
+    match arguments.get(index) {
+        Some(callee @ Value::Callable(_)) => Ok(callee),
+        _ => Err(type_error(function, "a function")),
+    }
+}
+
+fn map(interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    let elements = elements("map", &arguments, 0)?;
+    let callback = function_arg("map", &arguments, 1)?;
+    let mapped = elements
+        .into_iter()
+        .map(|element| interpreter.call_value(callback, vec![element]))
+        .collect::<Result<_, _>>()?;
+    Ok(Value::list(mapped))
+}
+
+fn filter(interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    let elements = elements("filter", &arguments, 0)?;
+    let callback = function_arg("filter", &arguments, 1)?;
+    let mut kept = Vec::new();
+    for element in elements {
+        if interpreter
+            .call_value(callback, vec![element.clone()])?
+            .is_truthy()
+        {
+            kept.push(element);
+        }
+    }
+    Ok(Value::list(kept))
+}
+
+/// Folds the list from the left with `f(accumulator, element)`, starting
+/// from `initial` or, without one, from the first element.
+fn reduce(interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    let mut elements = elements("reduce", &arguments, 0)?.into_iter();
+    let callback = function_arg("reduce", &arguments, 1)?;
+    let mut accumulator = match arguments.get(2) {
+        Some(initial) => initial.clone(),
+        None => elements
+            .next()
+            .ok_or_else(|| invalid_argument("reduce() of an empty list needs an initial value."))?,
+    };
+    for element in elements {
+        accumulator = interpreter.call_value(callback, vec![accumulator, element])?;
+    }
+    Ok(accumulator)
+}
+
+/// Whether `f` is truthy for any element, stopping at the first that is.
+fn any(interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    let callback = function_arg("any", &arguments, 1)?;
+    for element in elements("any", &arguments, 0)? {
+        if interpreter.call_value(callback, vec![element])?.is_truthy() {
+            return Ok(Value::Boolean(true));
+        }
+    }
+    Ok(Value::Boolean(false))
+}
+
+/// Whether `f` is truthy for every element, stopping at the first that is
+/// not.
+fn all(interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    let callback = function_arg("all", &arguments, 1)?;
+    for element in elements("all", &arguments, 0)? {
+        if !interpreter.call_value(callback, vec![element])?.is_truthy() {
+            return Ok(Value::Boolean(false));
+        }
+    }
+    Ok(Value::Boolean(true))
+}
+
+/// The first element for which `f` is truthy, or `nil`.
+fn find(interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    let callback = function_arg("find", &arguments, 1)?;
+    for element in elements("find", &arguments, 0)? {
+        if interpreter
+            .call_value(callback, vec![element.clone()])?
+            .is_truthy()
+        {
+            return Ok(element);
+        }
+    }
+    Ok(Value::Nil)
+}
+
+/// A sorted copy of the list. Without a comparator, elements must be all
+/// numbers or all strings. A comparator `f(a, b)` returns a negative number
+/// when `a` comes first, a positive one when `b` does and zero when either
+/// order will do. The sort is stable.
+fn sort(interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    let elements = elements("sort", &arguments, 0)?;
+    let sorted = match arguments.get(1) {
+        Some(_) => {
+            let comparator = function_arg("sort", &arguments, 1)?;
+            merge_sort(elements, &mut |a, b| {
+                let order = interpreter.call_value(comparator, vec![a.clone(), b.clone()])?;
+                order
+                    .compare_numbers(&Value::Int(0))
+                    .ok_or_else(|| type_error("sort", "a comparator that returns a number"))
+            })?
+        }
+        None => merge_sort(elements, &mut natural_order)?,
+    };
+    Ok(Value::list(sorted))
+}
+
+fn natural_order(a: &Value, b: &Value) -> Result<Ordering, RuntimeError> {
+    match (a, b) {
+        (Value::Str(a), Value::Str(b)) => Ok(a.cmp(b)),
+        _ => a
+            .compare_numbers(b)
+            .ok_or_else(|| type_error("sort", "a list of numbers or of strings")),
+    }
+}
+
+/// A stable merge sort that stops at the first error from `compare`. The
+/// standard library's sorts are not used because a comparator written in Lox
+/// may fail or be inconsistent, and they allow neither.
+fn merge_sort(
+    mut elements: Vec<Value>,
+    compare: &mut dyn FnMut(&Value, &Value) -> Result<Ordering, RuntimeError>,
+) -> Result<Vec<Value>, RuntimeError> {
+    if elements.len() <= 1 {
+        return Ok(elements);
+    }
+    let right = elements.split_off(elements.len() / 2);
+    let left = merge_sort(elements, compare)?;
+    let right = merge_sort(right, compare)?;
+    let mut merged = Vec::with_capacity(left.len() + right.len());
+    let mut left = left.into_iter().peekable();
+    let mut right = right.into_iter().peekable();
+    while let (Some(a), Some(b)) = (left.peek(), right.peek()) {
+        if compare(a, b)? == Ordering::Greater {
+            merged.extend(right.next());
+        } else {
+            merged.extend(left.next());
+        }
+    }
+    merged.extend(left);
+    merged.extend(right);
+    Ok(merged)
+}
+
+fn reverse(_interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    let mut elements = elements("reverse", &arguments, 0)?;
+    elements.reverse();
+    Ok(Value::list(elements))
+}
+
+/// Pairs up the elements of two lists, stopping at the end of the shorter.
+fn zip(_interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    let first = elements("zip", &arguments, 0)?;
+    let second = elements("zip", &arguments, 1)?;
+    let pairs = first
+        .into_iter()
+        .zip(second)
+        .map(|(a, b)| Value::list(vec![a, b]))
+        .collect();
+    Ok(Value::list(pairs))
+}
+
+/// `[index, element]` pairs, with indices counting from zero.
+fn enumerate(_interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    let pairs = elements("enumerate", &arguments, 0)?
+        .into_iter()
+        .enumerate()
+        .map(|(index, element)| Value::list(vec![Value::Int(index as i64), element]))
+        .collect();
+    Ok(Value::list(pairs))
+}
@@ -0,0 +1,213 @@
+use super::{NativeModule, integer_arg, invalid_argument, number_arg, type_error};
+use crate::bigint::BigInt;
+use crate::interpreter::{self, Interpreter, RuntimeError};
+use crate::token_type::TokenType;
+use crate::value::Value;
+use std::cmp::Ordering;
+
+pub fn module() -> NativeModule {
+    NativeModule::new("math")
+        .constant("pi", Value::Number(std::f64::consts::PI))
+        .constant("e", Value::Number(std::f64::consts::E))
+        .function("sqrt", 1, sqrt)
+        .function("pow", 2, pow)
+        .function("abs", 1, abs)
+        .function("floor", 1, floor)
+        .function("ceil", 1, ceil)
+        .function("round", 1, round)
+        .function_with_arity("min", 1, None, min)
+        .function_with_arity("max", 1, None, max)
+        .function("sin", 1, sin)
+        .function("cos", 1, cos)
+        .function("tan", 1, tan)
+        .function("asin", 1, asin)
+        .function("acos", 1, acos)
+        .function("atan", 1, atan)
+        .function("atan2", 2, atan2)
+        .function_with_arity("log", 1, Some(2), log)
+        .function("exp", 1, exp)
+        .function("isnan", 1, isnan)
+        .function("isfinite", 1, isfinite)
+        .function("random", 0, random)
+        .function("randomInt", 2, random_int)
+}
+
+/// Applies a float function, turning a NaN result for non-NaN input into a
+/// domain error rather than letting it leak into the script.
+fn float_function(
+    name: &str,
+    arguments: &[Value],
+    function: impl Fn(&[f64]) -> f64,
+) -> Result<Value, RuntimeError> {
+    let inputs = (0..arguments.len())
+        .map(|index| number_arg(name, arguments, index))
+        .collect::<Result<Vec<_>, _>>()?;
+    let result = function(&inputs);
+    if result.is_nan() && !inputs.iter().any(|input| input.is_nan()) {
+        return Err(invalid_argument(format!(
+            "{name}() argument is outside its domain."
+        )));
+    }
+    Ok(Value::Number(result))
+}
+
+fn sqrt(_interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    float_function("sqrt", &arguments, |x| x[0].sqrt())
+}
+
+fn pow(_interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    // Integer powers stay exact, like `**`.
+    if let [
+        base @ (Value::Int(_) | Value::BigInt(_)),
+        exponent @ (Value::Int(_) | Value::BigInt(_)),
+    ] = arguments.as_slice()
+    {
+        return interpreter::integer_arithmetic(base, TokenType::StarStar, exponent)
+            .map_err(RuntimeError::from);
+    }
+    float_function("pow", &arguments, |x| x[0].powf(x[1]))
+}
+
+fn abs(_interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    match &arguments[0] {
+        Value::Int(number) => Ok(number.checked_abs().map_or_else(
+            || interpreter::normalize(-&BigInt::from(*number)),
+            Value::Int,
+        )),
+        Value::BigInt(number) if number.is_negative() => Ok(Value::BigInt(-number)),
+        Value::BigInt(number) => Ok(Value::BigInt(number.clone())),
+        _ => float_function("abs", &arguments, |x| x[0].abs()),
+    }
+}
+
+/// Rounds with `function` to an integer; integers are returned unchanged.
+fn to_integer(
+    name: &str,
+    arguments: &[Value],
+    function: fn(f64) -> f64,
+) -> Result<Value, RuntimeError> {
+    match &arguments[0] {
+        value @ (Value::Int(_) | Value::BigInt(_)) => Ok(value.clone()),
+        _ => {
+            let rounded = function(number_arg(name, arguments, 0)?);
+            BigInt::from_f64(rounded)
+                .map(interpreter::normalize)
+                .ok_or_else(|| {
+                    invalid_argument(format!("{name}() needs a finite number, got {rounded}."))
+                })
+        }
+    }
+}
+
+fn floor(_interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    to_integer("floor", &arguments, f64::floor)
+}
+
+fn ceil(_interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    to_integer("ceil", &arguments, f64::ceil)
+}
+
+/// Rounds half away from zero.
+fn round(_interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    to_integer("round", &arguments, f64::round)
+}
+
+/// The argument that `wanted` orders first; a NaN argument wins so it is not
+/// silently dropped.
+fn extreme(name: &str, arguments: Vec<Value>, wanted: Ordering) -> Result<Value, RuntimeError> {
+    let mut best: Option<Value> = None;
+    for (index, argument) in arguments.iter().enumerate() {
+        let number = number_arg(name, &arguments, index)?;
+        if number.is_nan() {
+            return Ok(argument.clone());
+        }
+        if best
+            .as_ref()
+            .is_none_or(|best| argument.compare_numbers(best) == Some(wanted))
+        {
+            best = Some(argument.clone());
+        }
+    }
+    best.ok_or_else(|| type_error(name, "at least one number"))
+}
+
+fn min(_interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    extreme("min", arguments, Ordering::Less)
+}
+
+fn max(_interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    extreme("max", arguments, Ordering::Greater)
+}
+
+fn sin(_interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    float_function("sin", &arguments, |x| x[0].sin())
+}
+
+fn cos(_interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    float_function("cos", &arguments, |x| x[0].cos())
+}
+
+fn tan(_interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    float_function("tan", &arguments, |x| x[0].tan())
+}
+
+fn asin(_interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    float_function("asin", &arguments, |x| x[0].asin())
+}
+
+fn acos(_interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    float_function("acos", &arguments, |x| x[0].acos())
+}
+
+fn atan(_interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    float_function("atan", &arguments, |x| x[0].atan())
+}
+
+fn atan2(_interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    float_function("atan2", &arguments, |x| x[0].atan2(x[1]))
+}
+
+/// The natural logarithm, or the logarithm in the given base.
+fn log(_interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    if number_arg("log", &arguments, 0)? == 0.0 {
+        return Err(invalid_argument("log() of zero is undefined."));
+    }
+    float_function("log", &arguments, |x| match x {
+        [x, base] => x.log(*base),
+        _ => x[0].ln(),
+    })
+}
+
+fn exp(_interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    float_function("exp", &arguments, |x| x[0].exp())
+}
+
+fn isnan(_interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    Ok(Value::Boolean(number_arg("isnan", &arguments, 0)?.is_nan()))
+}
+
+fn isfinite(_interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    match &arguments[0] {
+        Value::Int(_) | Value::BigInt(_) => Ok(Value::Boolean(true)),
+        _ => Ok(Value::Boolean(
+            number_arg("isfinite", &arguments, 0)?.is_finite(),
+        )),
+    }
+}
+
+/// A float in `[0, 1)`.
+fn random(interpreter: &mut Interpreter, _arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    Ok(Value::Number(interpreter.random().next_f64()))
+}
+
+/// An integer from `low` to `high`, both included.
+fn random_int(interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    let low = integer_arg("randomInt", &arguments, 0)?;
+    let high = integer_arg("randomInt", &arguments, 1)?;
+    if low > high {
+        return Err(invalid_argument(format!(
+            "randomInt() low {low} is above high {high}."
+        )));
+    }
+    Ok(Value::Int(interpreter.random().range(low, high)))
+}
@@ -0,0 +1,192 @@
+//! Native modules: groups of builtin functions and constants written in Rust.
+//!
+//! Each module is a [`NativeModule`] listing its members; the interpreter
+//! registers every module returned by [`modules`] as globals.
+
+mod core;
+mod file;
+mod format;
+mod json;
+mod list;
+mod math;
+mod string;
+mod system;
+
+use crate::environment::Environment;
+use crate::interpreter::{self, Interpreter, RuntimeError, RuntimeErrorKind};
+use crate::lox_callable::LoxCallable;
+use crate::value::Value;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+pub type NativeFn = fn(&mut Interpreter, Vec<Value>) -> Result<Value, RuntimeError>;
+
+/// A builtin function. `max_arity` is `None` for functions that take any
+/// number of arguments from `arity` up.
+pub struct NativeFunction {
+    name: &'static str,
+    arity: usize,
+    max_arity: Option<usize>,
+    function: NativeFn,
+}
+
+impl NativeFunction {
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+impl LoxCallable for NativeFunction {
+    fn arity(&self) -> usize {
+        self.arity
+    }
+
+    fn max_arity(&self) -> Option<usize> {
+        self.max_arity
+    }
+
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        arguments: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        (self.function)(interpreter, arguments)
+    }
+}
+
+/// A named group of native functions and constants.
+pub struct NativeModule {
+    pub name: &'static str,
+    members: Vec<(&'static str, Value)>,
+}
+
+impl NativeModule {
+    pub fn new(name: &'static str) -> Self {
+        NativeModule {
+            name,
+            members: Vec::new(),
+        }
+    }
+
+    /// Adds a function taking exactly `arity` arguments.
+    pub fn function(self, name: &'static str, arity: usize, function: NativeFn) -> Self {
+        self.function_with_arity(name, arity, Some(arity), function)
+    }
+
+    /// Adds a function taking between `arity` and `max_arity` arguments, or
+    /// any number from `arity` up when `max_arity` is `None`.
+    pub fn function_with_arity(
+        mut self,
+        name: &'static str,
+        arity: usize,
+        max_arity: Option<usize>,
+        function: NativeFn,
+    ) -> Self {
+        let native = NativeFunction {
+            name,
+            arity,
+            max_arity,
+            function,
+        };
+        self.members.push((name, Value::Callable(Rc::new(native))));
+        self
+    }
+
+    pub fn constant(mut self, name: &'static str, value: Value) -> Self {
+        self.members.push((name, value));
+        self
+    }
+
+    /// The members as a map, for a module reached through one global such as
+    /// `json.parse`.
+    pub fn to_map(&self) -> Value {
+        Value::map(
+            self.members
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.clone()))
+                .collect(),
+        )
+    }
+
+    /// Defines every member of the module in `environment`.
+    pub fn register(&self, environment: &mut Environment) {
+        for (name, value) in &self.members {
+            environment.define(name.to_string(), value.clone());
+        }
+    }
+}
+
+/// The modules every interpreter starts with.
+pub fn modules() -> Vec<NativeModule> {
+    vec![
+        core::module(),
+        math::module(),
+        string::module(),
+        list::module(),
+        format::module(),
+        file::module(),
+        system::module(),
+        json::module(),
+    ]
+}
+
+/// The error for an argument of the wrong type, such as `sqrt("4")`.
+pub(crate) fn type_error(function: &str, expected: &str) -> RuntimeError {
+    RuntimeErrorKind::TypeMismatch(format!("{function}() expects {expected}.")).into()
+}
+
+/// The error for an argument of the right type but an unusable value, such
+/// as `sqrt(-1)`.
+pub(crate) fn invalid_argument(message: impl Into<String>) -> RuntimeError {
+    RuntimeErrorKind::InvalidArgument(message.into()).into()
+}
+
+pub(crate) fn number_arg(
+    function: &str,
+    arguments: &[Value],
+    index: usize,
+) -> Result<f64, RuntimeError> {
+    arguments
+        .get(index)
+        .and_then(interpreter::as_float)
+        .ok_or_else(|| type_error(function, "a number"))
+}
+
+pub(crate) fn string_arg<'a>(
+    function: &str,
+    arguments: &'a [Value],
+    index: usize,
+) -> Result<&'a str, RuntimeError> {
+    match arguments.get(index) {
+        Some(Value::Str(text)) => Ok(text),
+        _ => Err(type_error(function, "a string")),
+    }
+}
+
+/// A whole-number argument, such as a count or a position.
+pub(crate) fn integer_arg(
+    function: &str,
+    arguments: &[Value],
+    index: usize,
+) -> Result<i64, RuntimeError> {
+    match arguments.get(index) {
+        Some(Value::Int(number)) => Ok(*number),
+        Some(Value::Number(number))
+            if number.fract() == 0.0 && (i64::MIN as f64..i64::MAX as f64).contains(number) =>
+        {
+            Ok(*number as i64)
+        }
+        _ => Err(type_error(function, "an integer")),
+    }
+}
+
+pub(crate) fn list_arg<'a>(
+    function: &str,
+    arguments: &'a [Value],
+    index: usize,
+) -> Result<&'a Rc<RefCell<Vec<Value>>>, RuntimeError> {
+    match arguments.get(index) {
+        Some(Value::List(list)) => Ok(list),
+        _ => Err(type_error(function, "a list")),
+    }
+}
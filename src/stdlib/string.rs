@@ -0,0 +1,194 @@
+//! String functions. Positions and lengths count Unicode scalar values, the
+//! same `char`s the scanner works in, rather than bytes.
+
+use super::{NativeModule, integer_arg, invalid_argument, list_arg, string_arg, type_error};
+use crate::interpreter::{self, Interpreter, RuntimeError, RuntimeErrorKind};
+use crate::value::Value;
+
+pub fn module() -> NativeModule {
+    NativeModule::new("string")
+        .function("len", 1, len)
+        .function_with_arity("substring", 2, Some(3), substring)
+        .function("indexOf", 2, index_of)
+        .function("contains", 2, contains)
+        .function("startsWith", 2, starts_with)
+        .function("endsWith", 2, ends_with)
+        .function("split", 2, split)
+        .function("join", 2, join)
+        .function("replace", 3, replace)
+        .function("upper", 1, upper)
+        .function("lower", 1, lower)
+        .function("trim", 1, trim)
+        .function("repeat", 2, repeat)
+        .function("charAt", 2, char_at)
+}
+
+/// The number of characters in a string, elements in a list or entries in
+/// a map.
+fn len(_interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    let length = match &arguments[0] {
+        Value::Str(text) => text.chars().count(),
+        Value::List(list) => list.borrow().len(),
+        Value::Map(map) => map.borrow().len(),
+        _ => return Err(type_error("len", "a string, a list or a map")),
+    };
+    Ok(Value::Int(length as i64))
+}
+
+/// The characters from `start` up to, but not including, `end`, which
+/// defaults to the end of the string.
+fn substring(_interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    let text = string_arg("substring", &arguments, 0)?;
+    let length = text.chars().count();
+    let start = integer_arg("substring", &arguments, 1)?;
+    let end = match arguments.get(2) {
+        Some(_) => integer_arg("substring", &arguments, 2)?,
+        None => length as i64,
+    };
+    for position in [start, end] {
+        if !(0..=length as i64).contains(&position) {
+            return Err(RuntimeErrorKind::IndexOutOfRange {
+                index: position,
+                length,
+            }
+            .into());
+        }
+    }
+    if end < start {
+        return Err(invalid_argument(format!(
+            "substring() end {end} is before start {start}."
+        )));
+    }
+    Ok(Value::Str(
+        text.chars()
+            .skip(start as usize)
+            .take((end - start) as usize)
+            .collect(),
+    ))
+}
+
+/// The position of the first occurrence of a substring, or of a value in a
+/// list, or -1 when there is none.
+fn index_of(_interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    let position = match &arguments[0] {
+        Value::Str(text) => {
+            let needle = string_arg("indexOf", &arguments, 1)?;
+            text.find(needle)
+                .map(|offset| text[..offset].chars().count())
+        }
+        Value::List(list) => list
+            .borrow()
+            .iter()
+            .position(|element| *element == arguments[1]),
+        _ => return Err(type_error("indexOf", "a string or a list")),
+    };
+    Ok(Value::Int(position.map_or(-1, |position| position as i64)))
+}
+
+fn contains(_interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    match &arguments[0] {
+        Value::Str(text) => Ok(Value::Boolean(
+            text.contains(string_arg("contains", &arguments, 1)?),
+        )),
+        Value::List(list) => Ok(Value::Boolean(list.borrow().contains(&arguments[1]))),
+        Value::Map(map) => Ok(Value::Boolean(
+            map.borrow()
+                .contains_key(string_arg("contains", &arguments, 1)?),
+        )),
+        _ => Err(type_error("contains", "a string, a list or a map")),
+    }
+}
+
+fn starts_with(
+    _interpreter: &mut Interpreter,
+    arguments: Vec<Value>,
+) -> Result<Value, RuntimeError> {
+    let text = string_arg("startsWith", &arguments, 0)?;
+    let prefix = string_arg("startsWith", &arguments, 1)?;
+    Ok(Value::Boolean(text.starts_with(prefix)))
+}
+
+fn ends_with(_interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    let text = string_arg("endsWith", &arguments, 0)?;
+    let suffix = string_arg("endsWith", &arguments, 1)?;
+    Ok(Value::Boolean(text.ends_with(suffix)))
+}
+
+/// Splits on every occurrence of the separator; an empty separator splits
+/// the string into its characters.
+fn split(_interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    let text = string_arg("split", &arguments, 0)?;
+    let separator = string_arg("split", &arguments, 1)?;
+    let parts: Vec<Value> = if separator.is_empty() {
+        text.chars().map(|c| Value::Str(c.to_string())).collect()
+    } else {
+        text.split(separator)
+            .map(|part| Value::Str(part.to_string()))
+            .collect()
+    };
+    Ok(Value::list(parts))
+}
+
+/// Joins the elements of a list, written as `print` would show them.
+fn join(_interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    let list = list_arg("join", &arguments, 0)?;
+    let separator = string_arg("join", &arguments, 1)?;
+    let parts: Vec<String> = list.borrow().iter().map(Value::to_string).collect();
+    Ok(Value::Str(parts.join(separator)))
+}
+
+fn replace(_interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    let text = string_arg("replace", &arguments, 0)?;
+    let from = string_arg("replace", &arguments, 1)?;
+    let to = string_arg("replace", &arguments, 2)?;
+    if from.is_empty() {
+        return Err(invalid_argument("replace() needs a non-empty pattern."));
+    }
+    Ok(Value::Str(text.replace(from, to)))
+}
+
+fn upper(_interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    Ok(Value::Str(
+        string_arg("upper", &arguments, 0)?.to_uppercase(),
+    ))
+}
+
+fn lower(_interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    Ok(Value::Str(
+        string_arg("lower", &arguments, 0)?.to_lowercase(),
+    ))
+}
+
+fn trim(_interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    Ok(Value::Str(
+        string_arg("trim", &arguments, 0)?.trim().to_string(),
+    ))
+}
+
+/// The longest string, in bytes, `repeat()` will build. Anything bigger is
+/// far more likely a mistake than a string the script can use.
+const MAX_REPEAT_LENGTH: usize = 1 << 30;
+
+fn repeat(_interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    let text = string_arg("repeat", &arguments, 0)?;
+    let count = integer_arg("repeat", &arguments, 1)?;
+    let count = usize::try_from(count)
+        .map_err(|_| invalid_argument(format!("repeat() count {count} is negative.")))?;
+    if text
+        .len()
+        .checked_mul(count)
+        .is_none_or(|length| length > MAX_REPEAT_LENGTH)
+    {
+        return Err(invalid_argument(format!(
+            "repeat() count {count} makes a string longer than {MAX_REPEAT_LENGTH} bytes."
+        )));
+    }
+    Ok(Value::Str(text.repeat(count)))
+}
+
+fn char_at(_interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    let text = string_arg("charAt", &arguments, 0)?;
+    integer_arg("charAt", &arguments, 1)?;
+    let position = interpreter::checked_index(&arguments[1], text.chars().count())?;
+    Ok(Value::Str(text.chars().nth(position).unwrap().to_string()))
+}
@@ -0,0 +1,81 @@
+//! The script's connection to the process that runs it: its command-line
+//! arguments, standard input, environment and exit code.
+
+use super::{NativeModule, integer_arg, invalid_argument, number_arg, string_arg};
+use crate::interpreter::{Interpreter, RuntimeError, RuntimeErrorKind};
+use crate::policy::io_error;
+use crate::value::Value;
+use std::env;
+use std::time::Duration;
+
+pub fn module() -> NativeModule {
+    NativeModule::new("system")
+        .function("args", 0, args)
+        .function("readLine", 0, read_line)
+        .function("readAll", 0, read_all)
+        .function_with_arity("exit", 0, Some(1), exit)
+        .function("getenv", 1, getenv)
+        .function("sleep", 1, sleep)
+}
+
+/// The arguments given after the script name, as a new list each call.
+fn args(interpreter: &mut Interpreter, _arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    Ok(Value::list(
+        interpreter
+            .args()
+            .iter()
+            .map(|arg| Value::Str(arg.clone()))
+            .collect(),
+    ))
+}
+
+/// The next line of input without its line ending, or `nil` once the input
+/// is exhausted, so `while ((line = readLine()) != nil)` reads every line.
+fn read_line(interpreter: &mut Interpreter, _arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    let line = interpreter
+        .read_line()
+        .map_err(|err| io_error("<stdin>", err))?;
+    Ok(line.map_or(Value::Nil, Value::Str))
+}
+
+fn read_all(interpreter: &mut Interpreter, _arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    let text = interpreter
+        .read_all()
+        .map_err(|err| io_error("<stdin>", err))?;
+    Ok(Value::Str(text))
+}
+
+/// Stops the script with an exit code, 0 by default. The code reaches the
+/// host process, so exiting needs host access like `getenv` and `sleep`.
+fn exit(interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    let code = match arguments.first() {
+        Some(_) => integer_arg("exit", &arguments, 0)?,
+        None => 0,
+    };
+    interpreter.policy().host_access("Exiting")?;
+    let code = i32::try_from(code)
+        .map_err(|_| invalid_argument(format!("exit() code {code} is out of range.")))?;
+    Err(RuntimeErrorKind::Exit(code).into())
+}
+
+/// The value of an environment variable, or `nil` when it is unset.
+fn getenv(interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    let name = string_arg("getenv", &arguments, 0)?;
+    interpreter.policy().host_access("Environment access")?;
+    Ok(env::var(name).map_or(Value::Nil, Value::Str))
+}
+
+/// Pauses for a number of seconds, which may be fractional.
+fn sleep(interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    let seconds = number_arg("sleep", &arguments, 0)?;
+    interpreter.policy().host_access("Sleeping")?;
+    let duration = Duration::try_from_secs_f64(seconds).map_err(|_| {
+        invalid_argument(if seconds >= 0.0 {
+            format!("sleep() of {seconds} seconds is too long.")
+        } else {
+            format!("sleep() needs a non-negative number of seconds, got {seconds}.")
+        })
+    })?;
+    interpreter.time_source().sleep(duration);
+    Ok(Value::Nil)
+}
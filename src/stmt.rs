@@ -1,6 +1,8 @@
 // src/stmt.rs
 use crate::expr::Expr;
 use crate::token::Token;
+use std::fmt;
+use std::rc::Rc;
 
 #[derive(Debug)]
 pub enum Stmt {
@@ -26,4 +28,68 @@ pub enum Stmt {
         condition: Expr,
         body: Box<Stmt>,
     },
+    Function {
+        name: Token,
+        params: Vec<Token>,
+        body: Rc<Vec<Stmt>>,
+    },
+    Return {
+        keyword: Token,
+        value: Option<Expr>,
+    },
+}
+
+impl fmt::Display for Stmt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Stmt::Expression { expression } => write!(f, "(; {})", expression),
+            Stmt::Print { expression } => write!(f, "(print {})", expression),
+            Stmt::Block { statements } => {
+                write!(f, "(block")?;
+                for statement in statements {
+                    write!(f, " {}", statement)?;
+                }
+                write!(f, ")")
+            }
+            Stmt::Var { name, initializer } => match initializer {
+                Some(initializer) => write!(f, "(var {} {})", name.lexeme, initializer),
+                None => write!(f, "(var {})", name.lexeme),
+            },
+            Stmt::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => match else_branch {
+                Some(else_branch) => {
+                    write!(f, "(if {} {} {})", condition, then_branch, else_branch)
+                }
+                None => write!(f, "(if {} {})", condition, then_branch),
+            },
+            Stmt::While { condition, body } => write!(f, "(while {} {})", condition, body),
+            Stmt::Function { name, params, body } => {
+                write!(f, "(fun {} ", name.lexeme)?;
+                write_function(f, params, body)?;
+                write!(f, ")")
+            }
+            Stmt::Return { value, .. } => match value {
+                Some(value) => write!(f, "(return {})", value),
+                None => write!(f, "(return)"),
+            },
+        }
+    }
+}
+
+/// Writes a parameter list and body, shared by function declarations and
+/// function expressions.
+pub(crate) fn write_function(
+    f: &mut fmt::Formatter<'_>,
+    params: &[Token],
+    body: &[Stmt],
+) -> fmt::Result {
+    let params: Vec<&str> = params.iter().map(|param| param.lexeme.as_str()).collect();
+    write!(f, "({})", params.join(" "))?;
+    for statement in body {
+        write!(f, " {}", statement)?;
+    }
+    Ok(())
 }
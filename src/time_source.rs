@@ -0,0 +1,56 @@
+//! Where the interpreter gets the time from, so runs can be made
+//! reproducible.
+
+use std::cell::Cell;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// The clock behind `clock()` and `sleep()`.
+pub trait TimeSource {
+    /// Seconds since the Unix epoch, or since whatever origin the source
+    /// chooses.
+    fn now(&self) -> f64;
+
+    /// Waits for `duration`, or pretends to.
+    fn sleep(&self, duration: Duration);
+}
+
+/// The real wall clock.
+pub struct SystemClock;
+
+impl TimeSource for SystemClock {
+    fn now(&self) -> f64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs_f64()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        thread::sleep(duration);
+    }
+}
+
+/// A clock that stands still until something sleeps, which advances it
+/// instantly. Scripts see time pass exactly as they ask for it.
+pub struct VirtualClock {
+    now: Cell<f64>,
+}
+
+impl VirtualClock {
+    pub fn new(start: f64) -> Self {
+        VirtualClock {
+            now: Cell::new(start),
+        }
+    }
+}
+
+impl TimeSource for VirtualClock {
+    fn now(&self) -> f64 {
+        self.now.get()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        self.now.set(self.now.get() + duration.as_secs_f64());
+    }
+}
@@ -1,3 +1,4 @@
+use crate::bigint::BigInt;
 use crate::token_type::TokenType;
 use std::fmt;
 
@@ -6,15 +7,42 @@ pub enum Literal {
     Identifier(String),
     Str(String),
     Number(f64),
+    Int(i64),
+    BigInt(BigInt),
     Bool(bool),
     Nil,
 }
 
+/// A half-open range of byte offsets into the source text.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    /// Which source the offsets point into: 0 for the one being rendered,
+    /// otherwise the id an [`App`](crate::app::App) session gave the script,
+    /// so a function defined by an earlier REPL line or `:load` still
+    /// reports errors against its own code.
+    pub source: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Span {
+            start,
+            end,
+            source: 0,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Token {
     pub token_type: TokenType,
     pub lexeme: String,
     pub line: usize,
+    /// 1-based column of the first character, counted in characters.
+    pub column: usize,
+    pub span: Span,
     pub literal: Option<Literal>,
 }
 
@@ -30,8 +58,16 @@ impl Token {
             lexeme,
             literal,
             line,
+            column: 0,
+            span: Span::default(),
         }
     }
+
+    pub fn with_position(mut self, column: usize, span: Span) -> Self {
+        self.column = column;
+        self.span = span;
+        self
+    }
 }
 
 impl fmt::Display for Literal {
@@ -40,6 +76,8 @@ impl fmt::Display for Literal {
             Literal::Identifier(value) => write!(f, "{}", value),
             Literal::Str(value) => write!(f, "{}", value),
             Literal::Number(value) => write!(f, "{}", value),
+            Literal::Int(value) => write!(f, "{}", value),
+            Literal::BigInt(value) => write!(f, "{}", value),
             Literal::Bool(value) => write!(f, "{}", value),
             Literal::Nil => write!(f, "nil"),
         }
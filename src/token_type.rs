@@ -5,27 +5,52 @@ pub enum TokenType {
     RightParen,
     LeftBrace,
     RightBrace,
+    LeftBracket,
+    RightBracket,
     Comma,
+    Colon,
     Dot,
     Minus,
+    Percent,
     Plus,
     Semicolon,
     Slash,
     Star,
+    Ampersand,
+    Pipe,
+    Caret,
+    Tilde,
 
     // One or two character tokens.
     Bang,
     BangEqual,
     Equal,
     EqualEqual,
+    Arrow,
     Greater,
     GreaterEqual,
+    GreaterGreater,
     Less,
     LessEqual,
+    LessLess,
+    MinusEqual,
+    MinusMinus,
+    PercentEqual,
+    PlusEqual,
+    PlusPlus,
+    Question,
+    QuestionQuestion,
+    SlashEqual,
+    StarEqual,
+    StarStar,
+    // Floor division; `//` already starts a comment.
+    TildeSlash,
 
     // Literals.
     Identifier,
     String,
+    // A string segment followed by `${`; the embedded expression comes next.
+    Interpolation,
     Number,
 
     // Keywords.
@@ -46,5 +71,10 @@ pub enum TokenType {
     Var,
     While,
 
+    // Only produced when the scanner is asked to keep comments.
+    Comment,
+    // Stands in for a lexeme the scanner reported an error for.
+    Error,
+
     EOF,
 }
@@ -1,24 +1,48 @@
+use crate::bigint::BigInt;
+use crate::json;
 use crate::lox_callable::LoxCallable;
+use crate::lox_map::LoxMap;
+use std::cell::{Cell, RefCell};
+use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
 use std::fmt;
+use std::hash::{Hash, Hasher};
 use std::rc::Rc;
 
 #[derive(Clone)]
 pub enum Value {
     Number(f64),
+    Int(i64),
+    /// An integer outside the `i64` range; arithmetic produces one only when
+    /// the result does not fit in `Int`.
+    BigInt(BigInt),
     Str(String),
     Boolean(bool),
     Nil,
     Callable(Rc<dyn LoxCallable>),
+    /// A mutable list shared by reference, like the lists of most scripting
+    /// languages: assigning a list to another variable does not copy it.
+    List(Rc<RefCell<Vec<Value>>>),
+    /// A mutable map from strings to values, shared by reference like lists.
+    Map(Rc<RefCell<LoxMap>>),
 }
 
 impl fmt::Debug for Value {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Value::Number(value) => write!(f, "Number({})", value),
+            Value::Int(value) => write!(f, "Int({})", value),
+            Value::BigInt(value) => write!(f, "BigInt({})", value),
             Value::Str(value) => write!(f, "Str({:?})", value),
             Value::Boolean(value) => write!(f, "Boolean({})", value),
             Value::Nil => write!(f, "Nil"),
             Value::Callable(_) => write!(f, "Callable(<dyn LoxCallable>)"),
+            Value::List(list) => write_container(f, Rc::as_ptr(list) as *const (), "[...]", |f| {
+                f.debug_tuple("List").field(&list.borrow()).finish()
+            }),
+            Value::Map(map) => write_container(f, Rc::as_ptr(map) as *const (), "{...}", |f| {
+                f.debug_tuple("Map").field(&map.borrow()).finish()
+            }),
         }
     }
 }
@@ -26,17 +50,44 @@ impl fmt::Debug for Value {
 impl PartialEq for Value {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
-            (Value::Number(a), Value::Number(b)) => a == b,
+            (
+                Value::Number(_) | Value::Int(_) | Value::BigInt(_),
+                Value::Number(_) | Value::Int(_) | Value::BigInt(_),
+            ) => self.compare_numbers(other) == Some(Ordering::Equal),
             (Value::Str(a), Value::Str(b)) => a == b,
             (Value::Boolean(a), Value::Boolean(b)) => a == b,
             (Value::Nil, Value::Nil) => true,
             (Value::Callable(a), Value::Callable(b)) => Rc::ptr_eq(a, b),
+            (Value::List(a), Value::List(b)) => {
+                Rc::ptr_eq(a, b)
+                    || compare_containers(
+                        Rc::as_ptr(a) as *const (),
+                        Rc::as_ptr(b) as *const (),
+                        || *a.borrow() == *b.borrow(),
+                    )
+            }
+            (Value::Map(a), Value::Map(b)) => {
+                Rc::ptr_eq(a, b)
+                    || compare_containers(
+                        Rc::as_ptr(a) as *const (),
+                        Rc::as_ptr(b) as *const (),
+                        || *a.borrow() == *b.borrow(),
+                    )
+            }
             _ => false,
         }
     }
 }
 
 impl Value {
+    pub fn list(values: Vec<Value>) -> Self {
+        Value::List(Rc::new(RefCell::new(values)))
+    }
+
+    pub fn map(map: LoxMap) -> Self {
+        Value::Map(Rc::new(RefCell::new(map)))
+    }
+
     pub fn is_truthy(&self) -> bool {
         match self {
             Value::Nil => false,
@@ -44,16 +95,259 @@ impl Value {
             _ => true,
         }
     }
+
+    /// The name `type()` reports. Integers and floats are both numbers.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Number(_) | Value::Int(_) | Value::BigInt(_) => "number",
+            Value::Str(_) => "string",
+            Value::Boolean(_) => "boolean",
+            Value::Nil => "nil",
+            Value::Callable(_) => "function",
+            Value::List(_) => "list",
+            Value::Map(_) => "map",
+        }
+    }
+
+    /// Orders two numbers by their exact values, so an int and a float are
+    /// equal only when the float is that whole number. Returns `None` for
+    /// non-numbers and NaN.
+    pub fn compare_numbers(&self, other: &Value) -> Option<Ordering> {
+        match (self, other) {
+            (Value::Int(a), Value::Int(b)) => Some(a.cmp(b)),
+            (Value::Number(a), Value::Number(b)) => a.partial_cmp(b),
+            (Value::Int(a), Value::Number(b)) => compare_int_to_float(*a, *b),
+            (Value::Number(a), Value::Int(b)) => {
+                compare_int_to_float(*b, *a).map(Ordering::reverse)
+            }
+            (Value::BigInt(a), Value::BigInt(b)) => Some(a.cmp(b)),
+            (Value::BigInt(a), Value::Int(b)) => Some(a.cmp(&BigInt::from(*b))),
+            (Value::Int(a), Value::BigInt(b)) => Some(BigInt::from(*a).cmp(b)),
+            (Value::BigInt(a), Value::Number(b)) => compare_big_to_float(a, *b),
+            (Value::Number(a), Value::BigInt(b)) => {
+                compare_big_to_float(b, *a).map(Ordering::reverse)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Hashing agrees with `==`: numbers that compare equal hash alike whatever
+/// their variant, so `3`, `3.0` and a whole float beyond `i64` that equals a
+/// big integer all land in the same bucket as their integer form.
+impl Hash for Value {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self {
+            Value::Int(value) => {
+                0u8.hash(state);
+                value.hash(state);
+            }
+            Value::BigInt(value) => {
+                1u8.hash(state);
+                value.hash(state);
+            }
+            Value::Number(value) => {
+                if value.fract() == 0.0 && (I64_RANGE_START..I64_RANGE_END).contains(value) {
+                    Value::Int(*value as i64).hash(state)
+                } else if let Some(big) = BigInt::from_f64(*value) {
+                    Value::BigInt(big).hash(state)
+                } else {
+                    2u8.hash(state);
+                    value.to_bits().hash(state);
+                }
+            }
+            Value::Str(value) => {
+                3u8.hash(state);
+                value.hash(state);
+            }
+            Value::Boolean(value) => {
+                4u8.hash(state);
+                value.hash(state);
+            }
+            Value::Nil => 5u8.hash(state),
+            Value::Callable(callable) => {
+                6u8.hash(state);
+                (Rc::as_ptr(callable) as *const () as usize).hash(state);
+            }
+            Value::List(list) => {
+                7u8.hash(state);
+                let list = list.borrow();
+                hash_contents(state, list.len(), |state| list.hash(state));
+            }
+            Value::Map(map) => {
+                // Equal maps may list their entries in different orders, so
+                // combine the entry hashes with an order-independent sum.
+                8u8.hash(state);
+                let map = map.borrow();
+                hash_contents(state, map.len(), |state| {
+                    let mut sum = 0u64;
+                    for entry in map.iter() {
+                        let mut hasher = DefaultHasher::new();
+                        entry.hash(&mut hasher);
+                        sum = sum.wrapping_add(hasher.finish());
+                    }
+                    sum.hash(state);
+                });
+            }
+        }
+    }
+}
+
+fn compare_big_to_float(big: &BigInt, float: f64) -> Option<Ordering> {
+    if float.is_nan() {
+        None
+    } else if float.is_infinite() {
+        Some(if float > 0.0 {
+            Ordering::Less
+        } else {
+            Ordering::Greater
+        })
+    } else {
+        let whole = BigInt::from_f64(float.trunc())?;
+        match big.cmp(&whole) {
+            Ordering::Equal => 0.0.partial_cmp(&float.fract()),
+            ordering => Some(ordering),
+        }
+    }
+}
+
+// Every i64 lies in [-2^63, 2^63), and both ends are exact floats.
+const I64_RANGE_START: f64 = -9_223_372_036_854_775_808.0;
+const I64_RANGE_END: f64 = 9_223_372_036_854_775_808.0;
+
+fn compare_int_to_float(int: i64, float: f64) -> Option<Ordering> {
+    if float.is_nan() {
+        None
+    } else if float >= I64_RANGE_END {
+        Some(Ordering::Less)
+    } else if float < I64_RANGE_START {
+        Some(Ordering::Greater)
+    } else {
+        // The truncated float fits in an i64 exactly; its fraction breaks ties.
+        match int.cmp(&(float.trunc() as i64)) {
+            Ordering::Equal => 0.0.partial_cmp(&float.fract()),
+            ordering => Some(ordering),
+        }
+    }
 }
 
 impl fmt::Display for Value {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Value::Number(value) => write!(f, "{}", value),
+            Value::Int(value) => write!(f, "{}", value),
+            Value::BigInt(value) => write!(f, "{}", value),
             Value::Str(value) => write!(f, "{}", value),
             Value::Boolean(value) => write!(f, "{}", value),
             Value::Nil => write!(f, "nil"),
             Value::Callable(_) => write!(f, "<callable>"),
+            Value::List(list) => write_container(f, Rc::as_ptr(list) as *const (), "[...]", |f| {
+                write!(f, "[")?;
+                for (index, element) in list.borrow().iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write_element(f, element)?;
+                }
+                write!(f, "]")
+            }),
+            Value::Map(map) => write_container(f, Rc::as_ptr(map) as *const (), "{...}", |f| {
+                write!(f, "{{")?;
+                for (index, (key, value)) in map.borrow().iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}: ", json::quote(key))?;
+                    write_element(f, value)?;
+                }
+                write!(f, "}}")
+            }),
+        }
+    }
+}
+
+/// Writes a value inside a list or map. Nested strings are quoted so
+/// `["a, b"]` and `["a", "b"]` print differently.
+fn write_element(f: &mut fmt::Formatter<'_>, value: &Value) -> fmt::Result {
+    match value {
+        Value::Str(text) => write!(f, "{}", json::quote(text)),
+        _ => write!(f, "{}", value),
+    }
+}
+
+thread_local! {
+    /// The lists and maps currently being displayed, innermost last.
+    static DISPLAYING: RefCell<Vec<*const ()>> = const { RefCell::new(Vec::new()) };
+    /// The pairs of lists or maps currently being compared, innermost last.
+    static COMPARING: RefCell<Vec<(*const (), *const ())>> = const { RefCell::new(Vec::new()) };
+    /// Whether a list or map is being hashed further out.
+    static HASHING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Compares two lists or maps with `body`. A pair already being compared
+/// further out counts as equal there, so two structures that contain
+/// themselves in the same shape compare equal instead of recursing forever;
+/// any difference still shows up along some other path. Distinct structures
+/// nested deeper than [`json::MAX_DEPTH`] are never equal, as comparing them
+/// could run out of stack.
+fn compare_containers(a: *const (), b: *const (), body: impl FnOnce() -> bool) -> bool {
+    let pair = (a, b);
+    let comparing = COMPARING.with(|open| {
+        let mut open = open.borrow_mut();
+        if open.len() >= json::MAX_DEPTH {
+            return Some(false);
+        }
+        let comparing = open.contains(&pair);
+        if !comparing {
+            open.push(pair);
+        }
+        comparing.then_some(true)
+    });
+    if let Some(equal) = comparing {
+        return equal;
+    }
+    let equal = body();
+    COMPARING.with(|open| open.borrow_mut().pop());
+    equal
+}
+
+/// Hashes the contents of a list or map with `body`, or only its length if
+/// it sits inside another list or map being hashed. Going one level deep
+/// keeps hashing linear however the structure shares or cycles back to its
+/// parts, bounds the recursion however deep it nests, and stays consistent
+/// with equality: equal structures have the same lengths everywhere.
+fn hash_contents<H: Hasher>(state: &mut H, length: usize, body: impl FnOnce(&mut H)) {
+    if HASHING.with(Cell::get) {
+        length.hash(state);
+        return;
+    }
+    HASHING.with(|hashing| hashing.set(true));
+    body(state);
+    HASHING.with(|hashing| hashing.set(false));
+}
+
+/// Writes a list or map with `body`, or `placeholder` if it is already being
+/// written further out, so a structure containing itself prints as
+/// `[1, [...]]` instead of recursing forever. Structures nested deeper than
+/// [`json::MAX_DEPTH`] are cut off with the placeholder too.
+fn write_container(
+    f: &mut fmt::Formatter<'_>,
+    pointer: *const (),
+    placeholder: &str,
+    body: impl FnOnce(&mut fmt::Formatter<'_>) -> fmt::Result,
+) -> fmt::Result {
+    let elided = DISPLAYING.with(|open| {
+        let mut open = open.borrow_mut();
+        let elided = open.len() >= json::MAX_DEPTH || open.contains(&pointer);
+        if !elided {
+            open.push(pointer);
         }
+        elided
+    });
+    if elided {
+        return write!(f, "{}", placeholder);
     }
+    let result = body(f);
+    DISPLAYING.with(|open| open.borrow_mut().pop());
+    result
 }
@@ -0,0 +1,72 @@
+use rblox::bigint::BigInt;
+use rblox::value::Value;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+fn big(digits: &str) -> BigInt {
+    match digits.strip_prefix('-') {
+        Some(digits) => -&BigInt::parse(digits, 10).expect("digits should parse"),
+        None => BigInt::parse(digits, 10).expect("digits should parse"),
+    }
+}
+
+fn hash(value: &Value) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[test]
+fn multiplies_divides_and_prints_exactly() {
+    let a = big("123456789012345678901234567890");
+    let b = big("987654321098765432109876543210");
+    let product = &a * &b;
+    assert_eq!(
+        product.to_string(),
+        "121932631137021795226185032733622923332237463801111263526900"
+    );
+    assert_eq!(product.div_rem_floor(&b), Some((a.clone(), BigInt::zero())));
+    assert_eq!((&(&product - &a) + &a), product);
+
+    let (quotient, remainder) = big("-1267650600228229401496703205376")
+        .div_rem_floor(&BigInt::from(7))
+        .expect("divisor is not zero");
+    assert_eq!(quotient.to_string(), "-181092942889747057356671886483");
+    assert_eq!(remainder, BigInt::from(5));
+    assert_eq!(a.div_rem_floor(&BigInt::zero()), None);
+
+    assert!(big("-18446744073709551616") < BigInt::from(i64::MIN));
+    assert_eq!(BigInt::from(i64::MIN).to_i64(), Some(i64::MIN));
+    assert_eq!(big("9223372036854775808").to_i64(), None);
+    assert_eq!(BigInt::parse("ff_ff", 16), Some(BigInt::from(0xffff)));
+}
+
+#[test]
+fn equal_numbers_hash_alike_across_representations() {
+    let two_to_64 = Value::BigInt(big("18446744073709551616"));
+    assert_eq!(two_to_64, Value::Number(18_446_744_073_709_551_616.0));
+    assert_eq!(
+        hash(&two_to_64),
+        hash(&Value::Number(18_446_744_073_709_551_616.0))
+    );
+    assert_eq!(Value::Int(3), Value::Number(3.0));
+    assert_eq!(hash(&Value::Int(3)), hash(&Value::Number(3.0)));
+    assert_eq!(hash(&Value::Int(0)), hash(&Value::Number(-0.0)));
+    assert_ne!(two_to_64, Value::Number(1.5e19));
+}
+
+#[test]
+fn bitwise_operators_act_on_twos_complement() {
+    let two_to_64 = big("18446744073709551616");
+    let minus_one = BigInt::from(-1);
+    assert_eq!(&two_to_64 & &minus_one, two_to_64);
+    assert_eq!(&two_to_64 | &BigInt::from(1), big("18446744073709551617"));
+    assert_eq!(
+        &-&two_to_64 | &BigInt::from(1),
+        big("-18446744073709551615")
+    );
+    assert_eq!(&two_to_64 ^ &minus_one, big("-18446744073709551617"));
+    assert_eq!(&big("-4294967296") & &big("4294967295"), BigInt::zero());
+    assert_eq!(two_to_64.shr(64), BigInt::from(1));
+    assert_eq!(big("-18446744073709551617").shr(64), BigInt::from(-2));
+}
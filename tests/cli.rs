@@ -0,0 +1,82 @@
+use rblox::app::App;
+use rblox::cli::{ColorChoice, Command, Input};
+use rblox::policy::Policy;
+
+fn args(list: &[&str]) -> Vec<String> {
+    list.iter().map(|arg| arg.to_string()).collect()
+}
+
+fn parse_args(args: &[String]) -> Result<Command, String> {
+    rblox::cli::parse_args(args).map(|cli| cli.command)
+}
+
+#[test]
+fn parses_subcommands_and_inputs() {
+    assert_eq!(parse_args(&args(&[])), Ok(Command::Repl));
+    assert_eq!(
+        parse_args(&args(&["script.lox"])),
+        Ok(Command::Run(Input::File("script.lox".to_string()), vec![]))
+    );
+    assert_eq!(
+        parse_args(&args(&["check", "-e", "print 1;"])),
+        Ok(Command::Check(Input::Inline("print 1;".to_string())))
+    );
+    assert_eq!(
+        parse_args(&args(&["ast", "-"])),
+        Ok(Command::Ast(Input::Stdin))
+    );
+    assert_eq!(
+        parse_args(&args(&["run", "a.lox", "b.lox", "--fast"])),
+        Ok(Command::Run(
+            Input::File("a.lox".to_string()),
+            args(&["b.lox", "--fast"])
+        ))
+    );
+    assert_eq!(
+        parse_args(&args(&["-e", "print args();", "x"])),
+        Ok(Command::Run(
+            Input::Inline("print args();".to_string()),
+            args(&["x"])
+        ))
+    );
+    assert!(parse_args(&args(&["check", "a.lox", "b.lox"])).is_err());
+    assert!(parse_args(&args(&["tokens", "--bogus"])).is_err());
+}
+
+#[test]
+fn parses_global_options_before_the_command() {
+    let cli = rblox::cli::parse_args(&args(&["--color=never", "check", "a.lox"])).unwrap();
+    assert_eq!(cli.options.color, ColorChoice::Never);
+    assert_eq!(
+        cli.command,
+        Command::Check(Input::File("a.lox".to_string()))
+    );
+    assert!(rblox::cli::parse_args(&args(&["--color", "sometimes"])).is_err());
+
+    let cli = rblox::cli::parse_args(&args(&["--error-format", "json", "x.lox"])).unwrap();
+    assert!(cli.options.json_errors);
+
+    let cli = rblox::cli::parse_args(&args(&["--host-access", "deny", "x.lox"])).unwrap();
+    assert_eq!(cli.options.policy, Policy::Deny);
+    let cli = rblox::cli::parse_args(&args(&["--host-access=jail", "x.lox"])).unwrap();
+    assert_eq!(cli.options.policy, Policy::Sandbox("jail".into()));
+
+    let cli = rblox::cli::parse_args(&args(&["--seed", "7", "x.lox"])).unwrap();
+    assert_eq!(cli.options.seed, Some(7));
+    assert!(rblox::cli::parse_args(&args(&["--seed", "-1", "x.lox"])).is_err());
+}
+
+#[test]
+fn check_reports_resolution_errors_and_fmt_keeps_comments() {
+    assert!(App::check_source("var a = 1; print a;").is_ok());
+    assert!(App::check_source("{ var a = a; }").is_err());
+
+    let formatted = App::format_source("var a=1;// one\nif(a){print -a;}").unwrap();
+    assert_eq!(formatted, "var a = 1; // one\nif (a) {\n    print -a;\n}\n");
+
+    let formatted = App::format_source("if(a){var m={\"k\":[1],\"n\":{}};print m.k[0];}").unwrap();
+    assert_eq!(
+        formatted,
+        "if (a) {\n    var m = {\"k\": [1], \"n\": {}};\n    print m.k[0];\n}\n"
+    );
+}
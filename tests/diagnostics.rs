@@ -0,0 +1,78 @@
+use rblox::app::App;
+use rblox::diagnostic::DiagnosticFormat;
+
+#[test]
+fn renders_errors_with_position_and_underline() {
+    let mut app = App::new();
+    let err = app
+        .run_named("demo.lox", "var a = 1;\nprint a + \"x\";\n")
+        .expect_err("adding a number to a string should fail");
+    assert_eq!(
+        err,
+        "error[E0300]: Operands must be two numbers or two strings.\n \
+         --> demo.lox:2:9\n  \
+         |\n\
+         2 | print a + \"x\";\n  \
+         |         ^"
+    );
+
+    let err = app
+        .run_named("demo.lox", "print missing;")
+        .expect_err("undefined variable should fail");
+    assert!(err.contains("demo.lox:1:7"));
+    assert!(err.ends_with("^^^^^^^"));
+}
+
+#[test]
+fn renders_errors_against_the_script_that_raised_them() {
+    let mut app = App::new();
+    app.run_named(
+        "lib.lox",
+        "var unused = 0;\nfun half(x) {\n  return x / 0;\n}\n",
+    )
+    .expect("defining a function should succeed");
+    let err = app
+        .run_named("<repl>", "half(4);")
+        .expect_err("dividing by zero should fail");
+    assert_eq!(
+        err,
+        "error[E0301]: Division by zero.\n \
+         --> lib.lox:3:12\n  \
+         |\n\
+         3 |   return x / 0;\n  \
+         |            ^"
+    );
+}
+
+#[test]
+fn emits_one_json_object_per_diagnostic() {
+    let mut app = App::new();
+    app.set_diagnostic_format(DiagnosticFormat::Json);
+    let err = app
+        .run_named("bad \"name\".lox", "var a = 1;\nprint (a;")
+        .expect_err("missing ')' should fail");
+    assert_eq!(
+        err,
+        "{\"severity\":\"error\",\"code\":\"E0100\",\
+         \"message\":\"Expect ')' after expression.\",\
+         \"file\":\"bad \\\"name\\\".lox\",\
+         \"start\":{\"line\":2,\"column\":9},\"end\":{\"line\":2,\"column\":10}}"
+    );
+}
+
+#[test]
+fn check_warns_about_redeclarations_that_still_run() {
+    let source = "{\n  var a = 1;\n  var a = 2;\n  print a;\n}\n";
+    let warnings = App::check_source(source).expect("a redeclaration is not an error");
+    let mut app = App::new();
+    app.set_diagnostic_format(DiagnosticFormat::Json);
+    assert_eq!(
+        app.render("demo.lox", source, &warnings),
+        "{\"severity\":\"warning\",\"code\":\"W0200\",\
+         \"message\":\"Already a variable with this name in this scope.\",\
+         \"file\":\"demo.lox\",\
+         \"start\":{\"line\":3,\"column\":7},\"end\":{\"line\":3,\"column\":8}}"
+    );
+    app.run_named("demo.lox", source)
+        .expect("running ignores warnings");
+}
@@ -1,8 +1,12 @@
 use rblox::app::App;
 use rblox::expr::Expr;
+use rblox::policy::Policy;
 use rblox::token::Token;
 use rblox::token_type::TokenType;
 use rblox::value::Value;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::thread;
 
 fn ident(name: &str) -> Token {
     Token::new(TokenType::Identifier, name.to_string(), None, 1)
@@ -25,7 +29,9 @@ fn load_fixture(name: &str) -> String {
 #[test]
 fn interprets_statements_fixture() {
     let source = load_fixture("statements.lox");
-    let mut app = run_source(&source);
+    let mut app = App::new();
+    app.interpreter_mut().set_deterministic(0);
+    app.run_source(&source).expect("run should succeed");
     let interpreter = app.interpreter_mut();
 
     let a = interpreter
@@ -120,8 +126,843 @@ fn interprets_statements_fixture() {
     let t0 = interpreter
         .evaluate(&Expr::Variable { name: ident("t0") })
         .expect("t0 should exist");
-    match t0 {
-        Value::Number(_) => {}
-        other => panic!("expected number, got {:?}", other),
-    }
+    assert_eq!(t0, Value::Number(0.0));
+}
+
+#[test]
+fn repl_commands_load_and_reset_the_session() {
+    let mut path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path.push("tests");
+    path.push("fixtures");
+    path.push("statements.lox");
+
+    let mut app = App::new();
+    app.run_command(&format!("load {}", path.display()))
+        .expect("load should succeed");
+    let names: Vec<String> = app
+        .interpreter_mut()
+        .globals()
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect();
+    assert!(names.contains(&"sum".to_string()));
+
+    app.run_source("var len = 3;").expect("run should succeed");
+    let defined: Vec<String> = app
+        .interpreter_mut()
+        .user_globals()
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect();
+    assert!(defined.contains(&"sum".to_string()));
+    assert!(defined.contains(&"len".to_string()));
+    assert!(!defined.contains(&"clock".to_string()));
+
+    app.run_command("reset").expect("reset should succeed");
+    let names: Vec<String> = app
+        .interpreter_mut()
+        .globals()
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect();
+    let builtins: Vec<String> = App::new()
+        .interpreter_mut()
+        .globals()
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect();
+    assert_eq!(names, builtins);
+    assert!(names.contains(&"clock".to_string()));
+    assert!(app.interpreter_mut().user_globals().is_empty());
+
+    assert!(app.run_command("ast 1 + 2 * 3").is_ok());
+    assert!(app.run_command("bogus").is_err());
+}
+
+fn global(app: &mut App, name: &str) -> Value {
+    app.interpreter_mut()
+        .evaluate(&Expr::Variable { name: ident(name) })
+        .unwrap_or_else(|err| panic!("{name} should exist: {err:?}"))
+}
+
+#[test]
+fn interpolates_expressions_into_strings() {
+    let mut app = run_source(
+        r#"
+        var n = 3;
+        var name = "lox";
+        var s = "n=${n}, next=${n + 1}, nested=${"<${name}>"}, ${nil}/${n > 2}";
+        var escaped = "cost \${n}";
+        var empty = "${""}";
+        "#,
+    );
+    assert_eq!(
+        global(&mut app, "s"),
+        Value::Str("n=3, next=4, nested=<lox>, nil/true".to_string())
+    );
+    assert_eq!(
+        global(&mut app, "escaped"),
+        Value::Str("cost ${n}".to_string())
+    );
+    assert_eq!(global(&mut app, "empty"), Value::Str(String::new()));
+}
+
+#[test]
+fn lambdas_and_arrow_functions_close_over_their_scope() {
+    let mut app = run_source(
+        r#"
+        fun makeCounter() {
+            var count = 0;
+            return fun () {
+                count = count + 1;
+                return count;
+            };
+        }
+        var counter = makeCounter();
+        counter();
+        var counted = counter();
+
+        fun apply(f, x) { return f(x); }
+        var doubled = apply((x) => x * 2, 21);
+        var add = (a, b) => a + b;
+        var sum = add(2, 3);
+        var greet = () => { return "hi"; };
+        var greeting = greet();
+        var grouped = (1 + 2);
+        "#,
+    );
+    assert_eq!(global(&mut app, "counted"), Value::Number(2.0));
+    assert_eq!(global(&mut app, "doubled"), Value::Number(42.0));
+    assert_eq!(global(&mut app, "sum"), Value::Number(5.0));
+    assert_eq!(global(&mut app, "greeting"), Value::Str("hi".to_string()));
+    assert_eq!(global(&mut app, "grouped"), Value::Number(3.0));
+
+    let mut app = App::new();
+    let err = app.run_source("return 1;").unwrap_err();
+    assert!(err.contains("Can't return from top-level code."));
+}
+
+#[test]
+fn runaway_recursion_is_a_runtime_error() {
+    // Debug builds need far more stack per Lox call than a test thread has.
+    let recurse = thread::Builder::new()
+        .stack_size(rblox::cli::STACK_SIZE)
+        .spawn(|| {
+            let mut app = App::new();
+            let err = app
+                .run_source("fun f(n) { return f(n + 1); } f(0);")
+                .unwrap_err();
+            assert!(err.contains("Stack overflow."), "{err}");
+
+            let mut app = run_source(
+                r#"
+                fun down(n) { return n == 0 ? "done" : down(n - 1); }
+                var deep = down(900);
+                "#,
+            );
+            assert_eq!(global(&mut app, "deep"), Value::Str("done".to_string()));
+        })
+        .unwrap();
+    recurse.join().unwrap();
+}
+
+#[test]
+fn conditional_and_nil_coalescing_evaluate_lazily() {
+    let mut app = run_source(
+        r#"
+        var calls = 0;
+        fun touch() { calls = calls + 1; return "touched"; }
+        var picked = true ? "yes" : touch();
+        var nested = false ? 1 : nil ? 2 : 3;
+        var fallback = nil ?? "default";
+        var kept = false ?? touch();
+        var chained = nil ?? nil ?? 7;
+        "#,
+    );
+    assert_eq!(global(&mut app, "picked"), Value::Str("yes".to_string()));
+    assert_eq!(global(&mut app, "nested"), Value::Number(3.0));
+    assert_eq!(
+        global(&mut app, "fallback"),
+        Value::Str("default".to_string())
+    );
+    assert_eq!(global(&mut app, "kept"), Value::Boolean(false));
+    assert_eq!(global(&mut app, "chained"), Value::Number(7.0));
+    assert_eq!(global(&mut app, "calls"), Value::Number(0.0));
+
+    assert_eq!(
+        App::dump_ast("print a ? b : c ? d : e ?? f;").unwrap(),
+        "(print (?: a b (?: c d (?? e f))))\n"
+    );
+}
+
+#[test]
+fn compound_assignment_and_increments_update_variables() {
+    let mut app = run_source(
+        r#"
+        var i = 5;
+        var post = i++;
+        var pre = ++i;
+        var down = i--;
+        i -= 2;
+        i *= 4;
+        i /= 2;
+        var negative = -7;
+        negative %= 3;
+        var s = "a";
+        s += "b";
+
+        var calls = 0;
+        fun next() { calls += 1; return calls; }
+        var total = 10;
+        total += next();
+        "#,
+    );
+    assert_eq!(global(&mut app, "post"), Value::Number(5.0));
+    assert_eq!(global(&mut app, "pre"), Value::Number(7.0));
+    assert_eq!(global(&mut app, "down"), Value::Number(7.0));
+    assert_eq!(global(&mut app, "i"), Value::Number(8.0));
+    assert_eq!(global(&mut app, "negative"), Value::Number(2.0));
+    assert_eq!(global(&mut app, "s"), Value::Str("ab".to_string()));
+    assert_eq!(global(&mut app, "total"), Value::Number(11.0));
+    assert_eq!(global(&mut app, "calls"), Value::Number(1.0));
+
+    let mut app = App::new();
+    let err = app.run_source("1++;").unwrap_err();
+    assert!(err.contains("Invalid increment target."));
+    let err = app.run_source("var t = \"x\"; t++;").unwrap_err();
+    assert!(err.contains("Operand must be a number."));
+}
+
+#[test]
+fn evaluates_modulo_power_floor_division_and_bitwise_operators() {
+    let mut app = run_source(
+        r#"
+        var modulo = -7 % 3;
+        var floored = -7 ~/ 2;
+        var power = 2 ** 3 ** 2;
+        var negated = -2 ** 2;
+        var bits = (6 & 3) + (6 | 3) * 10 + (6 ^ 3) * 100;
+        var inverted = ~5;
+        var shifted = (1 << 4) + (-16 >> 2);
+        var low_bit = 5 & 1 == 1;
+        var at_least = 3 >= 3;
+        "#,
+    );
+    assert_eq!(global(&mut app, "modulo"), Value::Number(2.0));
+    assert_eq!(global(&mut app, "floored"), Value::Number(-4.0));
+    assert_eq!(global(&mut app, "power"), Value::Number(512.0));
+    assert_eq!(global(&mut app, "negated"), Value::Number(-4.0));
+    assert_eq!(global(&mut app, "bits"), Value::Number(572.0));
+    assert_eq!(global(&mut app, "inverted"), Value::Number(-6.0));
+    assert_eq!(global(&mut app, "shifted"), Value::Number(12.0));
+    assert_eq!(global(&mut app, "low_bit"), Value::Boolean(true));
+    assert_eq!(global(&mut app, "at_least"), Value::Boolean(true));
+
+    let mut app = App::new();
+    let err = app.run_source("print 1.5 & 1;").unwrap_err();
+    assert!(err.contains("Operands must be integers."));
+    let err = app.run_source("print 1 ~/ 0;").unwrap_err();
+    assert!(err.contains("Division by zero."));
+}
+
+#[test]
+fn integers_stay_exact_and_promote_to_floats() {
+    let mut app = run_source(
+        r#"
+        var id = 9007199254740993;
+        var next = id + 1;
+        var half = 7 / 2;
+        var floored = 7 ~/ -2;
+        var mixed = 1 + 0.5;
+        var same = 3 == 3.0;
+        var inexact = 9007199254740993 == 9007199254740992.0;
+        var power = 2 ** 62;
+        "#,
+    );
+    assert_eq!(global(&mut app, "id"), Value::Int(9_007_199_254_740_993));
+    assert_eq!(global(&mut app, "next"), Value::Int(9_007_199_254_740_994));
+    assert_eq!(global(&mut app, "half"), Value::Number(3.5));
+    assert_eq!(global(&mut app, "floored"), Value::Int(-4));
+    assert_eq!(global(&mut app, "mixed"), Value::Number(1.5));
+    assert_eq!(global(&mut app, "same"), Value::Boolean(true));
+    assert_eq!(global(&mut app, "inexact"), Value::Boolean(false));
+    assert_eq!(global(&mut app, "power"), Value::Int(1 << 62));
+    assert!(matches!(global(&mut app, "power"), Value::Int(_)));
+
+    let mut app = App::new();
+    let err = app.run_source("print 2 ** 10000000000;").unwrap_err();
+    assert!(err.contains("error[E0304]: Integer overflow."));
+}
+
+#[test]
+fn integer_overflow_promotes_to_big_integers() {
+    let mut app = run_source(
+        r#"
+        var total = 9223372036854775807;
+        total += 1;
+        var power = 2 ** 100;
+        var back = power ~/ 2 ** 40;
+        var rest = -power % 7;
+        var counter = 9223372036854775807;
+        counter++;
+        var top = 1 << 63;
+        var masked = [top | 1, top & -1, top ^ top, top >> 63, -top >> 62, ~top];
+        var wrapped = (top << 10) >> 10 == top;
+        "#,
+    );
+    assert_eq!(global(&mut app, "total").to_string(), "9223372036854775808");
+    assert_eq!(
+        global(&mut app, "power").to_string(),
+        "1267650600228229401496703205376"
+    );
+    assert!(matches!(
+        global(&mut app, "back"),
+        Value::Int(1_152_921_504_606_846_976)
+    ));
+    assert_eq!(global(&mut app, "rest"), Value::Int(5));
+    assert_eq!(global(&mut app, "counter"), global(&mut app, "total"));
+    assert_eq!(global(&mut app, "top"), global(&mut app, "total"));
+    assert_eq!(
+        global(&mut app, "masked").to_string(),
+        "[9223372036854775809, 9223372036854775808, 0, 1, -2, -9223372036854775809]"
+    );
+    assert_eq!(global(&mut app, "wrapped"), Value::Boolean(true));
+
+    let mut app = App::new();
+    let err = app.run_source("print 1 << (1 << 63);").unwrap_err();
+    assert!(err.contains("Shift amount must be between 0 and 63."));
+}
+
+#[test]
+fn math_module_computes_and_rejects_domain_errors() {
+    let mut app = run_source(
+        r#"
+        var root = sqrt(16);
+        var exact = pow(2, 100);
+        var floored = floor(-2.5);
+        var rounded = round(2.5);
+        var smallest = min(3, 1.5, 2);
+        var largest = max(1, 7, 3);
+        var angle = atan2(1, 1) * 4;
+        var logged = log(8, 2);
+        var finite = isfinite(1e308 * 10);
+        "#,
+    );
+    assert_eq!(global(&mut app, "root"), Value::Number(4.0));
+    assert_eq!(
+        global(&mut app, "exact").to_string(),
+        "1267650600228229401496703205376"
+    );
+    assert!(matches!(global(&mut app, "floored"), Value::Int(-3)));
+    assert!(matches!(global(&mut app, "rounded"), Value::Int(3)));
+    assert_eq!(global(&mut app, "smallest"), Value::Number(1.5));
+    assert_eq!(global(&mut app, "largest"), Value::Int(7));
+    assert_eq!(global(&mut app, "angle"), global(&mut app, "pi"));
+    assert_eq!(global(&mut app, "logged"), Value::Number(3.0));
+    assert_eq!(global(&mut app, "finite"), Value::Boolean(false));
+
+    let mut app = App::new();
+    let err = app.run_source("sqrt(-1);").unwrap_err();
+    assert!(err.contains("error[E0305]: sqrt() argument is outside its domain."));
+    let err = app.run_source("min();").unwrap_err();
+    assert!(err.contains("Expected at least 1 arguments but got 0."));
+}
+
+#[test]
+fn lists_index_and_string_functions_count_characters() {
+    let mut app = run_source(
+        r#"
+        var list = [1, "a", [2, 3],];
+        list[0] += 10;
+        list[2][1]++;
+        var word = "héllo";
+        var second = word[1];
+        var size = len(word);
+        var middle = substring(word, 1, 4);
+        var found = indexOf("añb", "b");
+        var parts = split("a,b,,c", ",");
+        var joined = join(parts, "-");
+        var shouted = upper(trim("  héllo "));
+        var swapped = replace("a-b-c", "-", "+");
+        "#,
+    );
+    assert_eq!(global(&mut app, "list").to_string(), r#"[11, "a", [2, 4]]"#);
+    assert_eq!(global(&mut app, "second"), Value::Str("é".to_string()));
+    assert_eq!(global(&mut app, "size"), Value::Int(5));
+    assert_eq!(global(&mut app, "middle"), Value::Str("éll".to_string()));
+    assert_eq!(global(&mut app, "found"), Value::Int(2));
+    assert_eq!(
+        global(&mut app, "parts").to_string(),
+        r#"["a", "b", "", "c"]"#
+    );
+    assert_eq!(global(&mut app, "joined"), Value::Str("a-b--c".to_string()));
+    assert_eq!(global(&mut app, "shouted"), Value::Str("HÉLLO".to_string()));
+    assert_eq!(global(&mut app, "swapped"), Value::Str("a+b+c".to_string()));
+
+    let mut app = App::new();
+    let err = app.run_source("[1, 2][2];").unwrap_err();
+    assert!(err.contains("error[E0306]: Index 2 is out of range for length 2."));
+    let err = app.run_source("charAt(\"abc\", -1);").unwrap_err();
+    assert!(err.contains("Index -1 is out of range for length 3."));
+    let err = app.run_source("\"abc\"[0] = \"x\";").unwrap_err();
+    assert!(err.contains("Only lists and maps support index assignment."));
+    let err = app
+        .run_source("repeat(\"ab\", 9223372036854775807);")
+        .unwrap_err();
+    assert!(err.contains("error[E0305]: repeat() count 9223372036854775807 makes a string longer"));
+    let err = app.run_source("repeat(\"ab\", 536870913);").unwrap_err();
+    assert!(err.contains("longer than 1073741824 bytes."));
+}
+
+#[test]
+fn converts_and_inspects_values() {
+    let mut app = run_source(
+        r#"
+        var parsed = num(" 42 ") + 1;
+        var fraction = num("-3.5e2");
+        var missing = num("forty") ?? 0;
+        var huge = [num("1e400"), num("-1e400")];
+        var large = num("1e300");
+        var text = str(1.5) + str([1, "a"]);
+        var truthy = bool(0);
+        var types = type(1) + type("") + type(nil) + type(clock) + type([]);
+        var params = arity(fun (a, b) {});
+        "#,
+    );
+    assert_eq!(global(&mut app, "parsed"), Value::Int(43));
+    assert_eq!(global(&mut app, "fraction"), Value::Number(-350.0));
+    assert_eq!(global(&mut app, "missing"), Value::Int(0));
+    assert_eq!(global(&mut app, "huge").to_string(), "[nil, nil]");
+    assert_eq!(global(&mut app, "large"), Value::Number(1e300));
+    assert_eq!(
+        global(&mut app, "text"),
+        Value::Str(r#"1.5[1, "a"]"#.to_string())
+    );
+    assert_eq!(global(&mut app, "truthy"), Value::Boolean(true));
+    assert_eq!(
+        global(&mut app, "types"),
+        Value::Str("numberstringnilfunctionlist".to_string())
+    );
+    assert_eq!(global(&mut app, "params"), Value::Int(2));
+
+    let mut app = App::new();
+    let err = app.run_source("arity(1);").unwrap_err();
+    assert!(err.contains("error[E0300]: arity() expects a function."));
+}
+
+#[test]
+fn file_access_follows_the_interpreter_policy() {
+    let root = std::env::temp_dir().join(format!("rblox-sandbox-{}", std::process::id()));
+    std::fs::create_dir_all(root.join("sub")).unwrap();
+
+    let mut app = App::new();
+    app.interpreter_mut()
+        .set_policy(Policy::Sandbox(root.clone()));
+    app.run_source(
+        r#"
+        writeFile("notes.txt", "one\n");
+        appendFile("sub/../notes.txt", "two\n");
+        var lines = readLines("notes.txt");
+        var entries = listDir(".");
+        var missing = exists("nope.txt");
+        "#,
+    )
+    .expect("run should succeed");
+    assert_eq!(global(&mut app, "lines").to_string(), r#"["one", "two"]"#);
+    assert_eq!(
+        global(&mut app, "entries").to_string(),
+        r#"["notes.txt", "sub"]"#
+    );
+    assert_eq!(global(&mut app, "missing"), Value::Boolean(false));
+    let err = app.run_source("readFile(\"../escape.txt\");").unwrap_err();
+    assert!(
+        err.contains(
+            "error[E0307]: Permission denied: Path '../escape.txt' is outside the sandbox."
+        )
+    );
+    let err = app.run_source("readFile(\"absent.txt\");").unwrap_err();
+    assert!(err.contains("error[E0303]: I/O error: absent.txt"));
+
+    app.interpreter_mut().set_policy(Policy::Deny);
+    let err = app.run_source("exists(\"notes.txt\");").unwrap_err();
+    assert!(err.contains("Permission denied: File access is not allowed."));
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn reads_script_arguments_and_input() {
+    let mut app = App::new();
+    app.interpreter_mut()
+        .set_args(vec!["in.txt".to_string(), "-v".to_string()]);
+    app.interpreter_mut()
+        .set_input(Box::new(std::io::Cursor::new(
+            "first\r\nsecond\nrest\nof it",
+        )));
+    app.run_source(
+        r#"
+        var arguments = args();
+        var first = readLine();
+        var second = readLine();
+        var rest = readAll();
+        var done = readLine();
+        "#,
+    )
+    .expect("run should succeed");
+    assert_eq!(
+        global(&mut app, "arguments").to_string(),
+        r#"["in.txt", "-v"]"#
+    );
+    assert_eq!(global(&mut app, "first"), Value::Str("first".to_string()));
+    assert_eq!(global(&mut app, "second"), Value::Str("second".to_string()));
+    assert_eq!(
+        global(&mut app, "rest"),
+        Value::Str("rest\nof it".to_string())
+    );
+    assert_eq!(global(&mut app, "done"), Value::Nil);
+}
+
+#[test]
+fn exit_unwinds_the_script_and_host_access_follows_the_policy() {
+    let mut app = run_source(
+        r#"
+        var reached = "before";
+        fun stop() { exit(3); }
+        stop();
+        reached = "after";
+        "#,
+    );
+    assert_eq!(app.exit_code(), Some(3));
+    assert_eq!(
+        global(&mut app, "reached"),
+        Value::Str("before".to_string())
+    );
+
+    let mut app =
+        run_source("var path = getenv(\"PATH\"); var unset = getenv(\"RBLOX_UNSET_VARIABLE\");");
+    assert_eq!(app.exit_code(), None);
+    assert!(matches!(global(&mut app, "path"), Value::Str(_)));
+    assert_eq!(global(&mut app, "unset"), Value::Nil);
+    let err = app.run_source("sleep(-1);").unwrap_err();
+    assert!(err.contains("sleep() needs a non-negative number of seconds, got -1."));
+    let err = app.run_source("sleep(1e30);").unwrap_err();
+    assert!(err.contains("sleep() of 1000000000000000000000000000000 seconds is too long."));
+
+    app.interpreter_mut()
+        .set_policy(Policy::Sandbox(std::env::temp_dir()));
+    let err = app.run_source("getenv(\"PATH\");").unwrap_err();
+    assert!(err.contains("error[E0307]: Permission denied: Environment access is not allowed."));
+    let err = app.run_source("exit(1);").unwrap_err();
+    assert!(err.contains("Permission denied: Exiting is not allowed."));
+    app.interpreter_mut().set_policy(Policy::Deny);
+    let err = app.run_source("sleep(0);").unwrap_err();
+    assert!(err.contains("Permission denied: Sleeping is not allowed."));
+    let err = app.run_source("exit();").unwrap_err();
+    assert!(err.contains("Permission denied: Exiting is not allowed."));
+    assert_eq!(app.exit_code(), None);
+}
+
+#[test]
+fn deterministic_mode_fixes_the_clock_and_random_numbers() {
+    let source = r#"
+        var start = clock();
+        sleep(1.5);
+        var elapsed = clock() - start;
+        var rolls = [randomInt(1, 6), randomInt(1, 6), randomInt(1, 6)];
+        var fraction = random();
+        "#;
+    let run = |seed| {
+        let mut app = App::new();
+        app.interpreter_mut().set_deterministic(seed);
+        app.run_source(source).expect("run should succeed");
+        app
+    };
+    let mut first = run(42);
+    let mut second = run(42);
+    assert_eq!(global(&mut first, "start"), Value::Number(0.0));
+    assert_eq!(global(&mut first, "elapsed"), Value::Number(1.5));
+    assert_eq!(global(&mut first, "rolls"), global(&mut second, "rolls"));
+    assert_eq!(
+        global(&mut first, "fraction"),
+        global(&mut second, "fraction")
+    );
+    let Value::List(rolls) = global(&mut first, "rolls") else {
+        panic!("rolls should be a list");
+    };
+    assert!(
+        rolls
+            .borrow()
+            .iter()
+            .all(|roll| matches!(roll, Value::Int(1..=6)))
+    );
+    assert!(matches!(global(&mut first, "fraction"), Value::Number(x) if (0.0..1.0).contains(&x)));
+
+    let err = first.run_source("randomInt(3, 1);").unwrap_err();
+    assert!(err.contains("randomInt() low 3 is above high 1."));
+}
+
+#[test]
+fn maps_keep_insertion_order_and_support_property_access() {
+    let mut app = run_source(
+        r#"
+        var config = {"name": "lox", "tags": ["a"], "limits": {"depth": 1},};
+        config.limits.depth += 1;
+        config["version"] = 2;
+        config.tags[0] = "b";
+        var missing = config["nope"] ?? "default";
+        var same = config == {"version": 2, "limits": {"depth": 2}, "tags": ["b"], "name": "lox"};
+        var cyclic = [1];
+        cyclic[0] = cyclic;
+        var shown = str(cyclic);
+        "#,
+    );
+    assert_eq!(
+        global(&mut app, "config").to_string(),
+        r#"{"name": "lox", "tags": ["b"], "limits": {"depth": 2}, "version": 2}"#
+    );
+    assert_eq!(
+        global(&mut app, "missing"),
+        Value::Str("default".to_string())
+    );
+    assert_eq!(global(&mut app, "same"), Value::Boolean(true));
+    assert_eq!(global(&mut app, "shown"), Value::Str("[[...]]".to_string()));
+
+    let err = app.run_source("config.nope;").unwrap_err();
+    assert!(err.contains("error[E0309]: Undefined property 'nope'."));
+    let err = app.run_source("var bad = {1: 2};").unwrap_err();
+    assert!(err.contains("Map keys must be strings."));
+}
+
+#[test]
+fn json_round_trips_and_reports_errors_with_positions() {
+    let mut app = run_source(
+        r#"
+        var data = json.parse("{\"id\": 12345678901234567890, \"ratio\": 0.5, \"tags\": [\"\\u00e9\", null], \"ok\": true}");
+        var compact = json.stringify(data);
+        var pretty = json.stringify({"a": [1, {}]}, 2);
+        var shared = [1];
+        var twice = json.stringify([shared, shared]);
+        "#,
+    );
+    assert_eq!(
+        global(&mut app, "compact"),
+        Value::Str(
+            r#"{"id":12345678901234567890,"ratio":0.5,"tags":["é",null],"ok":true}"#.to_string()
+        )
+    );
+    assert_eq!(
+        global(&mut app, "pretty"),
+        Value::Str("{\n  \"a\": [\n    1,\n    {}\n  ]\n}".to_string())
+    );
+    assert_eq!(
+        global(&mut app, "twice"),
+        Value::Str("[[1],[1]]".to_string())
+    );
+
+    let err = app
+        .run_source(r#"json.parse("[1,\n 2,, 3]");"#)
+        .unwrap_err();
+    assert!(err.contains("json.parse(): Expect a JSON value at line 2, column 4."));
+    let err = app
+        .run_source("var loop = {}; loop.self = loop; json.stringify(loop);")
+        .unwrap_err();
+    assert!(err.contains("json.stringify() cannot encode a cyclic structure."));
+    let err = app.run_source("json.stringify([clock]);").unwrap_err();
+    assert!(err.contains("json.stringify() cannot encode a function."));
+    let err = app
+        .run_source("json.stringify([1], 9000000000000000000);")
+        .unwrap_err();
+    assert!(err.contains("json.stringify() indent 9000000000000000000 is not between 0 and 10."));
+    let err = app
+        .run_source("var deep = []; for (var i = 0; i < 1000; i = i + 1) deep = [deep]; json.stringify(deep);")
+        .unwrap_err();
+    assert!(err.contains("json.stringify() cannot encode nesting deeper than 512 levels."));
+    app.run_source("var floatIndent = json.stringify([1], 2.0);")
+        .expect("run should succeed");
+    assert_eq!(
+        global(&mut app, "floatIndent"),
+        Value::Str("[\n  1\n]".to_string())
+    );
+}
+
+#[test]
+fn format_pads_aligns_and_rounds() {
+    let mut app = run_source(
+        r#"
+        var price = format("[{:>8.2}]", 3.14159);
+        var columns = format("{:<5}|{:^7}|{:*>4}", "ab", "mid", 7);
+        var ordered = format("{1} {0} {}", "a", "b");
+        var named = format("{name} is {age:03}", {"name": "Ann", "age": 7});
+        var kinds = format("{:+.1f} {:.2e} {:x} {:b} {{}}", 2, 1500, 255, -5);
+        var clipped = format("{:.3}", "abcdef");
+        "#,
+    );
+    assert_eq!(
+        global(&mut app, "price"),
+        Value::Str("[    3.14]".to_string())
+    );
+    assert_eq!(
+        global(&mut app, "columns"),
+        Value::Str("ab   |  mid  |***7".to_string())
+    );
+    assert_eq!(global(&mut app, "ordered"), Value::Str("b a a".to_string()));
+    assert_eq!(
+        global(&mut app, "named"),
+        Value::Str("Ann is 007".to_string())
+    );
+    assert_eq!(
+        global(&mut app, "kinds"),
+        Value::Str("+2.0 1.50e3 ff -101 {}".to_string())
+    );
+    assert_eq!(global(&mut app, "clipped"), Value::Str("abc".to_string()));
+
+    let err = app.run_source("format(\"{} {}\", 1);").unwrap_err();
+    assert!(err.contains("error[E0305]: format() has no argument 1."));
+    let err = app.run_source("format(\"{:q}\", 1);").unwrap_err();
+    assert!(err.contains("format() has an unknown format type 'q' in 'q'."));
+    let err = app.run_source("format(\"{oops\");").unwrap_err();
+    assert!(err.contains("format() has an unclosed '{'."));
+    let err = app.run_source("format(\"{:.70000}\", 1.5);").unwrap_err();
+    assert!(err.contains("format() has an invalid format spec '.70000'."));
+    let err = app
+        .run_source("format(\"{:99999999999999999999999}\", 1);")
+        .unwrap_err();
+    assert!(err.contains("format() has an invalid format spec '99999999999999999999999'."));
+    let mut app = run_source(
+        "var widest = len(format(\"{:65535}\", 1)); var longest = len(format(\"{:.65535}\", 0.5));",
+    );
+    assert_eq!(global(&mut app, "widest"), Value::Int(65535));
+    assert_eq!(global(&mut app, "longest"), Value::Int(65537));
+}
+
+#[test]
+fn list_functions_call_back_into_lox() {
+    let mut app = run_source(
+        r#"
+        var xs = [3, 1, 4, 1, 5];
+        var doubled = map(xs, fun (x) { return x * 2; });
+        var odd = filter(xs, fun (x) { return x % 2 == 1; });
+        var total = reduce(xs, fun (sum, x) { return sum + x; });
+        var joined = reduce(["a", "b"], fun (acc, s) { return acc + s; }, ">");
+        var checks = [any(xs, fun (x) { return x > 4; }), all(xs, fun (x) { return x > 1; })];
+        var found = [find(xs, fun (x) { return x > 3; }), find(xs, fun (x) { return x > 9; })];
+        var ascending = sort(xs);
+        var byLength = sort(["ccc", "a", "bb", "d"], fun (a, b) { return len(a) - len(b); });
+        var backwards = reverse(xs);
+        var pairs = zip([1, 2, 3], ["a", "b"]);
+        var indexed = enumerate(["x", "y"]);
+        "#,
+    );
+    assert_eq!(global(&mut app, "doubled").to_string(), "[6, 2, 8, 2, 10]");
+    assert_eq!(global(&mut app, "odd").to_string(), "[3, 1, 1, 5]");
+    assert_eq!(global(&mut app, "total"), Value::Int(14));
+    assert_eq!(global(&mut app, "joined"), Value::Str(">ab".to_string()));
+    assert_eq!(global(&mut app, "checks").to_string(), "[true, false]");
+    assert_eq!(global(&mut app, "found").to_string(), "[4, nil]");
+    assert_eq!(global(&mut app, "ascending").to_string(), "[1, 1, 3, 4, 5]");
+    assert_eq!(
+        global(&mut app, "byLength").to_string(),
+        r#"["a", "d", "bb", "ccc"]"#
+    );
+    assert_eq!(global(&mut app, "backwards").to_string(), "[5, 1, 4, 1, 3]");
+    assert_eq!(
+        global(&mut app, "pairs").to_string(),
+        r#"[[1, "a"], [2, "b"]]"#
+    );
+    assert_eq!(
+        global(&mut app, "indexed").to_string(),
+        r#"[[0, "x"], [1, "y"]]"#
+    );
+
+    let err = app
+        .run_source("map(xs, fun (x) {\n  return x / 0;\n});")
+        .unwrap_err();
+    assert!(err.contains("Division by zero."));
+    assert!(err.contains("2 |   return x / 0;"));
+    let err = app
+        .run_source("reduce([], fun (a, b) { return a; });")
+        .unwrap_err();
+    assert!(err.contains("reduce() of an empty list needs an initial value."));
+    let err = app
+        .run_source("map(xs, fun (a, b) { return a; });")
+        .unwrap_err();
+    assert!(err.contains("Expected 2 arguments but got 1."));
+    let err = app.run_source("sort([1, \"a\"]);").unwrap_err();
+    assert!(err.contains("sort() expects a list of numbers or of strings."));
+    let err = app
+        .run_source("sort(xs, fun (a, b) { return a < b; });")
+        .unwrap_err();
+    assert!(err.contains("sort() expects a comparator that returns a number."));
+}
+
+#[test]
+fn cyclic_lists_compare_and_hash_without_recursing() {
+    let mut app = run_source(
+        r#"
+        var a = [0];
+        a[0] = a;
+        var b = [0];
+        b[0] = b;
+        var c = [[0]];
+        c[0][0] = c;
+        var d = [1, 0];
+        d[1] = d;
+        var e = [2, 0];
+        e[1] = e;
+        var equal = [a == b, a == c, d == e, d == d];
+        var found = [contains([1, b], a), indexOf([d, e], e)];
+        var m = {"self": nil};
+        m.self = m;
+        var n = {"self": nil};
+        n.self = n;
+        var mapsEqual = m == n;
+        var wide = [0, 0];
+        wide[0] = wide;
+        wide[1] = wide;
+        var wideEqual = wide == [wide, wide];
+        "#,
+    );
+    assert_eq!(
+        global(&mut app, "equal").to_string(),
+        "[true, true, false, true]"
+    );
+    assert_eq!(global(&mut app, "found").to_string(), "[true, 1]");
+    assert_eq!(global(&mut app, "mapsEqual"), Value::Boolean(true));
+    assert_eq!(global(&mut app, "wideEqual"), Value::Boolean(true));
+
+    let hash = |value: &Value| {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    };
+    assert_eq!(hash(&global(&mut app, "a")), hash(&global(&mut app, "c")));
+    assert_eq!(hash(&global(&mut app, "m")), hash(&global(&mut app, "n")));
+    let wide = global(&mut app, "wide");
+    assert_eq!(
+        hash(&wide),
+        hash(&Value::list(vec![wide.clone(), wide.clone()]))
+    );
+    assert_eq!(format!("{:?}", global(&mut app, "a")), "List([[...]])");
+}
+
+#[test]
+fn deeply_nested_lists_print_and_compare_up_to_a_limit() {
+    let mut app = run_source(
+        r#"
+        fun nest(depth) {
+            var list = [];
+            for (var i = 0; i < depth; i++) list = [list];
+            return list;
+        }
+        var deep = nest(600);
+        var shallow = nest(100);
+        var text = str(deep);
+        var equal = [deep == nest(600), shallow == nest(100)];
+        "#,
+    );
+    let text = "[".repeat(512) + "[...]" + &"]".repeat(512);
+    assert_eq!(global(&mut app, "text"), Value::Str(text));
+    assert_eq!(global(&mut app, "equal").to_string(), "[false, true]");
+    let debug = format!("{:?}", global(&mut app, "deep"));
+    assert!(debug.contains("List([[...]])"), "{debug}");
 }
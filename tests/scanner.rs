@@ -0,0 +1,160 @@
+use rblox::scanner::Scanner;
+use rblox::token::Literal;
+use rblox::token_type::TokenType;
+
+fn token_types(source: &str) -> Vec<TokenType> {
+    let (tokens, errors) = Scanner::new(source).scan_tokens();
+    assert!(errors.is_empty(), "unexpected scan errors: {errors:?}");
+    tokens.iter().map(|token| token.token_type).collect()
+}
+
+#[test]
+fn keeps_scanning_after_lexical_errors() {
+    let (tokens, errors) = Scanner::new("var a = 1 @ 2;\n# print \"open").scan_tokens();
+    let messages: Vec<String> = errors.iter().map(|err| err.to_string()).collect();
+    assert_eq!(
+        messages,
+        vec![
+            "[line 1:11] Error: Unexpected character.",
+            "[line 2:1] Error: Unexpected character.",
+            "[line 2:9] Error: Unterminated string.",
+        ]
+    );
+
+    let types: Vec<TokenType> = tokens.iter().map(|token| token.token_type).collect();
+    assert_eq!(
+        types,
+        vec![
+            TokenType::Var,
+            TokenType::Identifier,
+            TokenType::Equal,
+            TokenType::Number,
+            TokenType::Error,
+            TokenType::Number,
+            TokenType::Semicolon,
+            TokenType::Error,
+            TokenType::Print,
+            TokenType::Error,
+            TokenType::EOF,
+        ]
+    );
+    assert_eq!(
+        token_types("print 1;"),
+        vec![
+            TokenType::Print,
+            TokenType::Number,
+            TokenType::Semicolon,
+            TokenType::EOF
+        ]
+    );
+}
+
+fn number(source: &str) -> f64 {
+    let (tokens, errors) = Scanner::new(source).scan_tokens();
+    assert!(errors.is_empty(), "unexpected scan errors: {errors:?}");
+    match tokens[0].literal {
+        Some(Literal::Number(value)) => value,
+        Some(Literal::Int(value)) => value as f64,
+        ref other => panic!("expected a number literal, got {other:?}"),
+    }
+}
+
+fn scan_errors(source: &str) -> Vec<String> {
+    let (_, errors) = Scanner::new(source).scan_tokens();
+    errors.iter().map(|err| err.to_string()).collect()
+}
+
+#[test]
+fn scans_exponent_radix_and_grouped_number_literals() {
+    assert_eq!(number("1e-9"), 1e-9);
+    assert_eq!(number("2.5E3"), 2500.0);
+    assert_eq!(number("0xFF"), 255.0);
+    assert_eq!(number("0o17"), 15.0);
+    assert_eq!(number("0b1010"), 10.0);
+    assert_eq!(number("1_000_000"), 1_000_000.0);
+    assert_eq!(number("0xdead_beef"), 3_735_928_559.0);
+
+    let (tokens, _) = Scanner::new("42 0x7fff_ffff_ffff_ffff 4.0").scan_tokens();
+    assert!(matches!(tokens[0].literal, Some(Literal::Int(42))));
+    assert!(matches!(tokens[1].literal, Some(Literal::Int(i64::MAX))));
+    assert!(matches!(tokens[2].literal, Some(Literal::Number(_))));
+
+    let (tokens, errors) =
+        Scanner::new("9223372036854775808 0x1_0000_0000_0000_0000").scan_tokens();
+    assert!(errors.is_empty(), "unexpected scan errors: {errors:?}");
+    let literals: Vec<String> = tokens[..2]
+        .iter()
+        .map(|token| match &token.literal {
+            Some(Literal::BigInt(value)) => value.to_string(),
+            other => panic!("expected a big integer literal, got {other:?}"),
+        })
+        .collect();
+    assert_eq!(
+        literals,
+        vec!["9223372036854775808", "18446744073709551616"]
+    );
+
+    assert_eq!(
+        scan_errors("0x 1e 0b102 1__0 1_ 2e+"),
+        vec![
+            "[line 1:1] Error: Expect digits after the radix prefix.",
+            "[line 1:4] Error: Expect digits in the exponent.",
+            "[line 1:7] Error: Invalid digit in number literal.",
+            "[line 1:13] Error: '_' must separate two digits.",
+            "[line 1:18] Error: '_' must separate two digits.",
+            "[line 1:21] Error: Expect digits in the exponent.",
+        ]
+    );
+}
+
+fn string(source: &str) -> String {
+    let (tokens, errors) = Scanner::new(source).scan_tokens();
+    assert!(errors.is_empty(), "unexpected scan errors: {errors:?}");
+    match &tokens[0].literal {
+        Some(Literal::Str(value)) => value.clone(),
+        other => panic!("expected a string literal, got {other:?}"),
+    }
+}
+
+#[test]
+fn decodes_escapes_and_raw_strings() {
+    assert_eq!(string(r#""a\tb\n\"q\" \\ \0""#), "a\tb\n\"q\" \\ \0");
+    assert_eq!(string(r#""\u{1F600}\u{e9}""#), "\u{1F600}\u{e9}");
+    assert_eq!(string(r#"r"C:\path\n""#), r"C:\path\n");
+    assert_eq!(string(r###"r#"say "hi""#"###), r#"say "hi""#);
+
+    assert_eq!(
+        scan_errors("\"ok\"\n\"\\q \\u{110000} \\u{} \\u41\""),
+        vec![
+            "[line 2:2] Error: Unknown escape sequence '\\q'.",
+            "[line 2:5] Error: '110000' is not a unicode scalar value.",
+            "[line 2:16] Error: A unicode escape needs one to six hex digits.",
+            "[line 2:21] Error: Expect '{' after '\\u'.",
+        ]
+    );
+}
+
+#[test]
+fn skips_nested_block_comments_and_reports_where_they_open() {
+    let (tokens, errors) =
+        Scanner::new("/* outer /* inner */\n still outer */ print\n/* x */ 1;").scan_tokens();
+    assert!(errors.is_empty());
+    let positions: Vec<(TokenType, usize)> = tokens
+        .iter()
+        .map(|token| (token.token_type, token.line))
+        .collect();
+    assert_eq!(
+        positions,
+        vec![
+            (TokenType::Print, 2),
+            (TokenType::Number, 3),
+            (TokenType::Semicolon, 3),
+            (TokenType::EOF, 3),
+        ]
+    );
+
+    assert_eq!(
+        scan_errors("print 1;\n  /* open /* nested */\n\n"),
+        vec!["[line 2:3] Error: Unterminated block comment."]
+    );
+}